@@ -76,4 +76,61 @@ pub enum TaskTraderError {
 
     #[msg("Numerical Overflow")]
     NumericalOverflow,
+
+    #[msg("Invalid Reviewer")]
+    InvalidReviewer,
+
+    #[msg("Too many reviewers")]
+    TooManyReviewers,
+
+    #[msg("Invalid fee basis points")]
+    InvalidFeeBps,
+
+    #[msg("Milestones must be non-zero and sum to the task amount")]
+    InvalidMilestones,
+
+    #[msg("Invalid milestone")]
+    InvalidMilestone,
+
+    #[msg("No approved milestone to withdraw")]
+    NoApprovedMilestone,
+
+    #[msg("Invalid Arbiter")]
+    InvalidArbiter,
+
+    #[msg("Referral splits exceed the rewards")]
+    InvalidReferralSplits,
+
+    #[msg("Change would undercut accepted applications")]
+    UndercutsAcceptedApplications,
+
+    #[msg("Batch entries do not match the accounts")]
+    InvalidBatch,
+
+    #[msg("Invalid attestation")]
+    InvalidAttestation,
+
+    #[msg("Attestation expired")]
+    AttestationExpired,
+
+    #[msg("Vesting needs a positive duration, a cliff within it and no milestones")]
+    InvalidVesting,
+
+    #[msg("Nothing has vested yet")]
+    NothingVested,
+
+    #[msg("Work URI is empty or too long")]
+    InvalidWorkUri,
+
+    #[msg("Task metadata is missing a title or exceeds its bounds")]
+    InvalidMetadata,
+
+    #[msg("Task not expired")]
+    TaskNotExpired,
+
+    #[msg("Task amount is locked while accepted applications vest")]
+    VestingInProgress,
+
+    #[msg("Dispute window has not lapsed yet")]
+    DisputeWindowOpen,
 }
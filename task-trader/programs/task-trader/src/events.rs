@@ -0,0 +1,221 @@
+use anchor_lang::prelude::*;
+
+use crate::state::task_info::{SbtGate, TaskMetadata, TaskState, Vesting};
+
+#[event]
+pub struct AdminUpdated {
+    pub signer: Pubkey,
+    pub task_fee_bps: u16,
+    pub reward_fee_bps: u16,
+    pub arbiter: Pubkey,
+}
+
+#[event]
+pub struct ReferralSplitsUpdated {
+    pub referral_splits_bps: [u16; 3],
+}
+
+#[event]
+pub struct ReferralRegistered {
+    pub user: Pubkey,
+    pub inviter: Pubkey,
+}
+
+#[event]
+pub struct SupportCoinsUpdated {
+    pub coin_mints: Vec<Pubkey>,
+}
+
+#[event]
+pub struct CoinFeeOverrideUpdated {
+    pub coin_mint: Pubkey,
+    pub task_fee_bps: Option<u16>,
+    pub reward_fee_bps: Option<u16>,
+}
+
+#[event]
+pub struct TreasuryInitialized {
+    pub coin_mint: Pubkey,
+}
+
+#[event]
+pub struct FeesCollected {
+    pub coin_mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub collected_fees: u64,
+}
+
+#[event]
+pub struct TaskCounterInitialized {
+    pub next_task_id: u64,
+}
+
+#[event]
+pub struct TaskCreated {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub requester: Pubkey,
+    pub coin_mint: Pubkey,
+    pub task_amount: u64,
+    pub taker_num: u64,
+    pub rewards: u64,
+    pub escrow_amount: u64,
+    pub apply_deadline: i64,
+    pub complete_deadline: i64,
+    pub milestones: Vec<u64>,
+    pub sbt_gate: Option<SbtGate>,
+    pub vesting: Option<Vesting>,
+    pub metadata: TaskMetadata,
+}
+
+/// Emitted whenever the amounts or seats of an existing task change.
+#[event]
+pub struct TaskAdjusted {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub task_amount: u64,
+    pub taker_num: u64,
+    pub rewards: u64,
+    pub escrow_amount: u64,
+}
+
+#[event]
+pub struct TaskMetadataUpdated {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub metadata: TaskMetadata,
+}
+
+#[event]
+pub struct TaskApplied {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub applicant: Pubkey,
+    pub inviter: Option<Pubkey>,
+}
+
+#[event]
+pub struct WorkSubmitted {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub applicant: Pubkey,
+    pub work_hash: [u8; 32],
+    pub work_uri: String,
+}
+
+#[event]
+pub struct ApplicationVerified {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub applicant: Pubkey,
+    pub reviewer: Pubkey,
+    pub is_accepted: bool,
+    pub rejection_reason: Option<u16>,
+    pub approved_milestones: u8,
+    pub awarded_amount: Option<u64>,
+}
+
+#[event]
+pub struct ApplicationCancelled {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub applicant: Pubkey,
+}
+
+#[event]
+pub struct ApplicationClosed {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub applicant: Pubkey,
+}
+
+/// Payout of the approved milestones of one accepted application. Referral
+/// rewards are paid with the final milestone: `level_rewards` lists what each
+/// referral level received and `inviter_rewards` is their sum. Rewards that no
+/// level received go to the treasury and are reported as `rewards_fee`.
+/// The part of the task amount the reviewer did not award is returned to the
+/// requester as `requester_refund`. On vesting tasks `claimed_amount` is the
+/// total payout received so far.
+#[event]
+pub struct RewardWithdrawn {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub applicant: Pubkey,
+    pub coin_mint: Pubkey,
+    pub task_amount: u64,
+    pub task_fee: u64,
+    pub inviter: Option<Pubkey>,
+    pub inviter_rewards: u64,
+    pub level_rewards: Vec<u64>,
+    pub rewards_fee: u64,
+    pub paid_milestones: u8,
+    pub requester_refund: u64,
+    pub claimed_amount: u64,
+}
+
+/// Summary of a batch review or payout. Entries that failed validation are
+/// listed in `skipped` by application address.
+#[event]
+pub struct ApplicationsBatchProcessed {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub processed: u32,
+    pub skipped: Vec<Pubkey>,
+}
+
+#[event]
+pub struct DisputeRaised {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub applicant: Pubkey,
+}
+
+#[event]
+pub struct DisputeLapsed {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub applicant: Pubkey,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub applicant: Pubkey,
+    pub arbiter: Pubkey,
+    pub applicant_amount: u64,
+    pub task_fee: u64,
+    pub requester_amount: u64,
+}
+
+#[event]
+pub struct TaskStateUpdated {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub state: TaskState,
+}
+
+/// Emitted when a task is closed, cancelled or reclaimed after expiry.
+#[event]
+pub struct TaskClosed {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub state: TaskState,
+    pub refund_amount: u64,
+}
+
+#[event]
+pub struct TaskAllowlistUpdated {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub allowed_applicants: Vec<Pubkey>,
+    pub allowlist_root: Option<[u8; 32]>,
+}
+
+#[event]
+pub struct TaskReviewersUpdated {
+    pub task_info: Pubkey,
+    pub task_id: u64,
+    pub reviewers: Vec<Pubkey>,
+}
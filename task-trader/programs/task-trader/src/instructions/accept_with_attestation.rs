@@ -0,0 +1,179 @@
+use crate::{
+    errors::TaskTraderError,
+    events::ApplicationVerified,
+    instructions::verify_task_application::{review_application, ApplicationReview},
+    state::admin::Admin,
+    state::attestation_nonce::AttestationNonce,
+    state::task_application::TaskApplication,
+    state::task_info::{TaskInfo, TaskState},
+    state::user_stats::UserStats,
+    utils::ed25519_utils,
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::sysvar::instructions::{
+        load_current_index_checked, load_instruction_at_checked,
+    },
+};
+
+/// Acceptance of an application signed off-chain by one of the task's reviewers.
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct AcceptanceAttestation {
+    pub task_id: u64,
+    pub applicant: Pubkey,
+    pub reviewer: Pubkey,
+    pub awarded_amount: Option<u64>,
+    pub expiry: i64,
+    pub nonce: u64,
+}
+
+impl AcceptanceAttestation {
+    pub const DOMAIN: &'static [u8] = b"task_trader_acceptance";
+
+    /// Bytes the reviewer signs: the domain tag, this program's ID and the
+    /// `task_info` address, followed by the Borsh encoding. The program and
+    /// account binding keeps a signature from being replayed on another
+    /// deployment or cluster, where task IDs repeat.
+    pub fn message(&self, task_info: &Pubkey) -> Result<Vec<u8>> {
+        let mut message = Self::DOMAIN.to_vec();
+        message.extend_from_slice(crate::ID.as_ref());
+        message.extend_from_slice(task_info.as_ref());
+        self.serialize(&mut message)?;
+        Ok(message)
+    }
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(attestation: AcceptanceAttestation)]
+pub struct AcceptWithAttestation<'info> {
+    #[account(mut)]
+    pub applicant: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = task_info.task_id == attestation.task_id @ TaskTraderError::InvalidTaskId,
+        constraint = task_info.is_authorized_reviewer(&attestation.reviewer, &admin.signer) @ TaskTraderError::InvalidReviewer,
+        constraint = task_info.state == TaskState::Open @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"task_application",
+            task_info.key().as_ref(),
+            applicant.key().as_ref(),
+        ],
+        bump,
+        constraint = attestation.applicant == applicant.key() @ TaskTraderError::InvalidApplicant,
+    )]
+    pub task_application: Account<'info, TaskApplication>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin: Account<'info, Admin>,
+
+    #[account(
+        init,
+        payer = applicant,
+        space = AttestationNonce::INIT_SPACE,
+        seeds = [
+            b"attestation_nonce",
+            attestation.reviewer.as_ref(),
+            attestation.nonce.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub attestation_nonce: Account<'info, AttestationNonce>,
+
+    #[account(
+        init_if_needed,
+        payer = applicant,
+        space = UserStats::INIT_SPACE,
+        seeds = [b"user_stats", applicant.key().as_ref(), task_info.coin_mint.as_ref()],
+        bump
+    )]
+    pub applicant_stats: Account<'info, UserStats>,
+
+    /// CHECK: The instructions sysvar, checked by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accepts an application with a reviewer's off-chain signature instead of a
+/// reviewer-signed transaction. The instruction right before this one must be
+/// an Ed25519 program instruction verifying the reviewer's signature over
+/// `attestation.message(task_info)`. The applicant submits it, typically together with
+/// `withdraw` in the same transaction.
+pub fn accept_with_attestation(
+    ctx: Context<AcceptWithAttestation>,
+    attestation: AcceptanceAttestation,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    if attestation.expiry < now {
+        return Err(TaskTraderError::AttestationExpired.into());
+    }
+    let task_info = &mut ctx.accounts.task_info;
+    if task_info.is_expired(now) {
+        return Err(TaskTraderError::TaskExpired.into());
+    }
+
+    let instructions = &ctx.accounts.instructions;
+    let current_index = load_current_index_checked(instructions)?;
+    let signature_index = current_index
+        .checked_sub(1)
+        .ok_or(TaskTraderError::InvalidAttestation)?;
+    let signature_instruction =
+        load_instruction_at_checked(signature_index as usize, instructions)?;
+    if !ed25519_utils::verifies_signature(
+        &signature_instruction,
+        &attestation.reviewer,
+        &attestation.message(&task_info.key())?,
+    ) {
+        return Err(TaskTraderError::InvalidAttestation.into());
+    }
+
+    let attestation_nonce = &mut ctx.accounts.attestation_nonce;
+    attestation_nonce.reviewer = attestation.reviewer;
+    attestation_nonce.nonce = attestation.nonce;
+
+    let task_application = &mut ctx.accounts.task_application;
+    let previous_state = task_application.state;
+    review_application(
+        task_info,
+        task_application,
+        &ApplicationReview {
+            is_accepted: true,
+            rejection_reason: None,
+            milestone_index: None,
+            awarded_amount: attestation.awarded_amount,
+        },
+        now,
+    )?;
+
+    let applicant_stats = &mut ctx.accounts.applicant_stats;
+    applicant_stats.initialize(
+        task_application.applicant,
+        task_info.coin_mint,
+        ctx.bumps.applicant_stats,
+    );
+    applicant_stats.record_review(previous_state, task_application.state)?;
+
+    emit_cpi!(ApplicationVerified {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        applicant: task_application.applicant,
+        reviewer: attestation.reviewer,
+        is_accepted: true,
+        rejection_reason: None,
+        approved_milestones: task_application.approved_milestones,
+        awarded_amount: task_application.awarded_amount,
+    });
+
+    Ok(())
+}
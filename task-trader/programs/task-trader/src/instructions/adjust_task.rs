@@ -0,0 +1,271 @@
+use crate::{
+    errors::TaskTraderError, events::TaskAdjusted, state::task_info::TaskInfo, utils::token_utils,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AdjustTask<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = task_info.requester == user.key() @ TaskTraderError::InvalidRequester,
+        constraint = task_info.is_active() @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+
+    /// CHECK: This is not dangerous
+    #[account(
+        seeds = [b"vault_authority", task_info.key().as_ref()],
+        bump,
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        constraint = coin_mint.key() == task_info.coin_mint @ TaskTraderError::InvalidMint,
+        mint::token_program = token_program,
+    )]
+    pub coin_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = coin_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_coin_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"task_vault", task_info.key().as_ref()],
+        bump,
+        constraint = task_vault.amount >= task_info.escrow_amount @ TaskTraderError::InvalidPoolAccount,
+    )]
+    pub task_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Adds seats to the task and escrows their task amount and rewards.
+pub fn increase_taker_num<'info>(
+    mut ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+    additional_takers: u64,
+) -> Result<()> {
+    let task_info = &mut ctx.accounts.task_info;
+    let deposit_amount = task_info
+        .amount_per_taker()?
+        .checked_mul(additional_takers)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+    task_info.taker_num = task_info
+        .taker_num
+        .checked_add(additional_takers)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+
+    deposit_escrow(&mut ctx, deposit_amount)
+}
+
+/// Raises the task amount of every seat that has not been paid out yet.
+/// Vesting tasks only allow it while no accepted application is vesting.
+pub fn increase_task_amount<'info>(
+    mut ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+    additional_amount: u64,
+) -> Result<()> {
+    let task_info = &mut ctx.accounts.task_info;
+    // Milestones must keep summing to the task amount
+    if !task_info.milestones.is_empty() {
+        return Err(TaskTraderError::InvalidMilestones.into());
+    }
+    // A vesting payout settles its whole fee with the first claim, so the fee
+    // on an increase after that would never leave the vault
+    if task_info.vesting.is_some() && task_info.accepted_count > task_info.paid_count {
+        return Err(TaskTraderError::VestingInProgress.into());
+    }
+    let deposit_amount = task_info
+        .unpaid_seats()?
+        .checked_mul(additional_amount)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+    task_info.task_amount = task_info
+        .task_amount
+        .checked_add(additional_amount)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+
+    deposit_escrow(&mut ctx, deposit_amount)
+}
+
+/// Raises the inviter rewards of every seat that has not been paid out yet.
+pub fn increase_rewards<'info>(
+    mut ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+    additional_rewards: u64,
+) -> Result<()> {
+    let task_info = &mut ctx.accounts.task_info;
+    let deposit_amount = task_info
+        .unpaid_seats()?
+        .checked_mul(additional_rewards)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+    task_info.rewards = task_info
+        .rewards
+        .checked_add(additional_rewards)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+
+    deposit_escrow(&mut ctx, deposit_amount)
+}
+
+/// Removes seats that no application has been accepted for or may still
+/// dispute, and refunds their escrow.
+pub fn decrease_taker_num<'info>(
+    mut ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+    removed_takers: u64,
+) -> Result<()> {
+    let task_info = &mut ctx.accounts.task_info;
+    let taker_num = task_info
+        .taker_num
+        .checked_sub(removed_takers)
+        .ok_or(TaskTraderError::UndercutsAcceptedApplications)?;
+    if taker_num == 0 || taker_num < task_info.reserved_seats()? {
+        return Err(TaskTraderError::UndercutsAcceptedApplications.into());
+    }
+    let refund_amount = task_info
+        .amount_per_taker()?
+        .checked_mul(removed_takers)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+    task_info.taker_num = taker_num;
+
+    refund_escrow(&mut ctx, refund_amount)
+}
+
+/// Lowers the task amount while no accepted application is waiting for payout.
+pub fn decrease_task_amount<'info>(
+    mut ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+    removed_amount: u64,
+) -> Result<()> {
+    let task_info = &mut ctx.accounts.task_info;
+    if !task_info.milestones.is_empty() {
+        return Err(TaskTraderError::InvalidMilestones.into());
+    }
+    if task_info.accepted_count > task_info.paid_count {
+        return Err(TaskTraderError::UndercutsAcceptedApplications.into());
+    }
+    let task_amount = task_info
+        .task_amount
+        .checked_sub(removed_amount)
+        .filter(|task_amount| *task_amount > 0)
+        .ok_or(TaskTraderError::InvalidAmount)?;
+    let refund_amount = task_info
+        .unpaid_seats()?
+        .checked_mul(removed_amount)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+    task_info.task_amount = task_amount;
+
+    refund_escrow(&mut ctx, refund_amount)
+}
+
+/// Lowers the inviter rewards while no accepted application is waiting for payout.
+pub fn decrease_rewards<'info>(
+    mut ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+    removed_rewards: u64,
+) -> Result<()> {
+    let task_info = &mut ctx.accounts.task_info;
+    if task_info.accepted_count > task_info.paid_count {
+        return Err(TaskTraderError::UndercutsAcceptedApplications.into());
+    }
+    let rewards = task_info
+        .rewards
+        .checked_sub(removed_rewards)
+        .ok_or(TaskTraderError::InvalidAmount)?;
+    let refund_amount = task_info
+        .unpaid_seats()?
+        .checked_mul(removed_rewards)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+    task_info.rewards = rewards;
+
+    refund_escrow(&mut ctx, refund_amount)
+}
+
+/// Moves `amount` from the requester into the vault, grossed up for transfer
+/// fees, and escrows what the vault actually received.
+fn deposit_escrow<'info>(
+    ctx: &mut Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Err(TaskTraderError::InvalidAmount.into());
+    }
+
+    let received_amount = token_utils::deposit_to_vault(
+        ctx.accounts.system_program.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.coin_mint,
+        ctx.accounts.user.to_account_info(),
+        &ctx.accounts.user_coin_account,
+        &mut ctx.accounts.task_vault,
+        ctx.remaining_accounts,
+        amount,
+    )?;
+    ctx.accounts.task_info.add_escrow(received_amount)?;
+
+    emit_adjusted(ctx)
+}
+
+/// Returns `amount` of the escrow to the requester.
+fn refund_escrow<'info>(
+    ctx: &mut Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Err(TaskTraderError::InvalidAmount.into());
+    }
+
+    let task_info_key = ctx.accounts.task_info.key();
+    let seeds = &[
+        b"vault_authority".as_ref(),
+        task_info_key.as_ref(),
+        &[ctx.bumps.vault_authority],
+    ];
+    token_utils::transfer_token_with_singer(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.task_vault.to_account_info(),
+        ctx.accounts.coin_mint.to_account_info(),
+        ctx.accounts.user_coin_account.to_account_info(),
+        ctx.accounts.vault_authority.to_account_info(),
+        ctx.remaining_accounts,
+        amount,
+        ctx.accounts.coin_mint.decimals,
+        Some(&[seeds]),
+    )?;
+
+    if token_utils::is_native_mint(&ctx.accounts.coin_mint.key()) {
+        token_utils::unwrap_sol(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_coin_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+        )?;
+    }
+
+    ctx.accounts.task_info.release_escrow(amount)?;
+
+    emit_adjusted(ctx)
+}
+
+fn emit_adjusted(ctx: &Context<AdjustTask>) -> Result<()> {
+    let task_info = &ctx.accounts.task_info;
+    emit_cpi!(TaskAdjusted {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        task_amount: task_info.task_amount,
+        taker_num: task_info.taker_num,
+        rewards: task_info.rewards,
+        escrow_amount: task_info.escrow_amount,
+    });
+
+    Ok(())
+}
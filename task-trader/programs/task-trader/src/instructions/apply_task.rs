@@ -1,10 +1,19 @@
+use crate::errors::TaskTraderError;
+use crate::events::TaskApplied;
+use crate::state::referral::Referral;
 use crate::state::task_application::TaskApplication;
-use crate::state::task_info::TaskInfo;
+use crate::state::task_info::{TaskInfo, TaskState};
+use crate::state::user_stats::UserStats;
+use crate::utils::sbt_utils;
 use anchor_lang::prelude::*;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ApplyTask<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = task_info.state == TaskState::Open @ TaskTraderError::InvalidTaskState,
+    )]
     pub task_info: Account<'info, TaskInfo>,
 
     #[account(
@@ -22,19 +31,79 @@ pub struct ApplyTask<'info> {
 
     #[account(mut)]
     pub applicant: Signer<'info>,
+
+    /// Created here so reviews and payouts of any kind can record to it
+    #[account(
+        init_if_needed,
+        payer = applicant,
+        space = UserStats::INIT_SPACE,
+        seeds = [b"user_stats", applicant.key().as_ref(), task_info.coin_mint.as_ref()],
+        bump
+    )]
+    pub applicant_stats: Account<'info, UserStats>,
+
+    /// CHECK: Referral of the applicant, naming its inviter. May be
+    /// uninitialized when the applicant has no inviter
+    #[account(
+        seeds = [b"referral", applicant.key().as_ref()],
+        bump,
+    )]
+    pub applicant_referral: UncheckedAccount<'info>,
+
+    /// CHECK: sbt-minter `SbtInfo` of the applicant, verified in the handler
+    pub applicant_sbt_info: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: sbt-minter `SbtInfo` of the inviter, verified in the handler
+    pub inviter_sbt_info: Option<UncheckedAccount<'info>>,
+
     pub system_program: Program<'info, System>,
 }
 
-pub fn apply_task(ctx: Context<ApplyTask>, inviter: Option<Pubkey>) -> Result<()> {
-    let task_info = &ctx.accounts.task_info;
-    let task_application = &mut ctx.accounts.task_application;
+/// Applies to the task. The inviter is the one registered in the applicant's
+/// referral, which is the only inviter payouts will reward.
+pub fn apply_task(ctx: Context<ApplyTask>, allowlist_proof: Option<Vec<[u8; 32]>>) -> Result<()> {
+    let task_info = &mut ctx.accounts.task_info;
     let applicant_key = ctx.accounts.applicant.key();
-    task_application.task_id = task_info.task_id;
-    task_application.applicant = applicant_key;
-    if let Some(inviter) = inviter {
-        if inviter != applicant_key {
-            task_application.inviter = inviter;
+    let inviter = Referral::load(&ctx.accounts.applicant_referral, &applicant_key)?
+        .map(|referral| referral.inviter);
+    if task_info.is_apply_expired(Clock::get()?.unix_timestamp) {
+        return Err(TaskTraderError::TaskExpired.into());
+    }
+    if !task_info.is_applicant_allowed(&applicant_key, allowlist_proof.as_deref()) {
+        return Err(TaskTraderError::ApplicantNotAllowed.into());
+    }
+    if let Some(sbt_gate) = task_info.sbt_gate {
+        let holds_sbt = |sbt_info: &Option<UncheckedAccount>, wallet: &Pubkey| {
+            sbt_info
+                .as_ref()
+                .is_some_and(|sbt_info| sbt_utils::holds_sbt(sbt_info, wallet, sbt_gate.min_score))
+        };
+        if !holds_sbt(&ctx.accounts.applicant_sbt_info, &applicant_key) {
+            return Err(TaskTraderError::ApplicantNotAllowed.into());
+        }
+        if let Some(inviter) = inviter.filter(|_| sbt_gate.require_inviter_sbt) {
+            if !holds_sbt(&ctx.accounts.inviter_sbt_info, &inviter) {
+                return Err(TaskTraderError::ApplicantNotAllowed.into());
+            }
         }
     }
+    task_info.record_application()?;
+    ctx.accounts.applicant_stats.initialize(
+        applicant_key,
+        task_info.coin_mint,
+        ctx.bumps.applicant_stats,
+    );
+
+    let task_application = &mut ctx.accounts.task_application;
+    task_application.task_id = task_info.task_id;
+    task_application.applicant = applicant_key;
+    task_application.inviter = inviter.unwrap_or_default();
+
+    emit_cpi!(TaskApplied {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        applicant: applicant_key,
+        inviter,
+    });
     Ok(())
 }
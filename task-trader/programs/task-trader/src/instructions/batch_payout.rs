@@ -0,0 +1,253 @@
+use crate::{
+    errors::TaskTraderError,
+    events::{ApplicationsBatchProcessed, RewardWithdrawn},
+    instructions::withdraw::{milestone_payout, record_withdrawal, MilestonePayout},
+    state::{
+        admin::Admin,
+        support_coin::SupportCoin,
+        task_application::{ApplicationState, TaskApplication},
+        task_info::{TaskInfo, TaskState},
+        treasury::Treasury,
+        user_stats::UserStats,
+    },
+    utils::token_utils,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BatchPayout<'info> {
+    /// The requester or any crank; payouts only go to the applicants' own accounts
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin: Account<'info, Admin>,
+
+    #[account(
+        seeds = [b"support_coin"],
+        bump,
+    )]
+    pub support_coin: Account<'info, SupportCoin>,
+
+    #[account(
+        mut,
+        constraint = task_info.state != TaskState::Cancelled @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+
+    /// CHECK: This is not dangerous
+    #[account(
+        seeds = [b"vault_authority", task_info.key().as_ref()],
+        bump,
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        constraint = coin_mint.key() == task_info.coin_mint @ TaskTraderError::InvalidMint,
+        mint::token_program = token_program,
+    )]
+    pub coin_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"task_vault", task_info.key().as_ref()],
+        bump,
+        constraint = task_vault.amount >= task_info.escrow_amount @ TaskTraderError::InvalidPoolAccount,
+    )]
+    pub task_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: This is not dangerous
+    #[account(
+        constraint = requester.key() == task_info.requester @ TaskTraderError::InvalidRequester
+    )]
+    pub requester: AccountInfo<'info>,
+
+    /// Receives the unawarded part of the task amounts
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = coin_mint,
+        associated_token::authority = requester,
+        associated_token::token_program = token_program,
+    )]
+    pub requester_coin_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", coin_mint.key().as_ref()],
+        bump = treasury.bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault", coin_mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Pays out many accepted applications of one task at once. `remaining_accounts`
+/// holds a `(TaskApplication, applicant token account, applicant UserStats)`
+/// triple per application; the token account must be the applicant's existing
+/// associated token account.
+///
+/// Entries that `withdraw` would reject are skipped and reported, as are final
+/// payouts with an inviter, since their referral accounts only fit `withdraw`,
+/// and applicants without a stats account.
+/// Native SOL is paid as wSOL and transfer-hook mints are not supported.
+pub fn batch_payout<'info>(ctx: Context<'_, '_, '_, 'info, BatchPayout<'info>>) -> Result<()> {
+    let entries = ctx.remaining_accounts.chunks_exact(3);
+    if !entries.remainder().is_empty() {
+        return Err(TaskTraderError::InvalidBatch.into());
+    }
+
+    let task_info_key = ctx.accounts.task_info.key();
+    let seeds = &[
+        b"vault_authority".as_ref(),
+        task_info_key.as_ref(),
+        &[ctx.bumps.vault_authority],
+    ];
+    let (task_fee_bps, _) = ctx.accounts.admin.fee_rates(
+        &ctx.accounts.support_coin,
+        &ctx.accounts.task_info.coin_mint,
+    );
+    let coin_mint = ctx.accounts.coin_mint.key();
+    let token_program = ctx.accounts.token_program.key();
+    let now = Clock::get()?.unix_timestamp;
+
+    let mut processed: u32 = 0;
+    let mut skipped = Vec::new();
+    for entry in entries {
+        let (application_account, applicant_coin_account, stats_account) =
+            (&entry[0], &entry[1], &entry[2]);
+        let task_info = &ctx.accounts.task_info;
+
+        let Some(mut task_application) =
+            TaskApplication::try_load(application_account, task_info.task_id)
+                .filter(|application| application.state == ApplicationState::AcceptedByAcceptance)
+        else {
+            skipped.push(application_account.key());
+            continue;
+        };
+        let is_applicant_account = applicant_coin_account.is_writable
+            && *applicant_coin_account.owner == token_program
+            && applicant_coin_account.key()
+                == get_associated_token_address_with_program_id(
+                    &task_application.applicant,
+                    &coin_mint,
+                    &token_program,
+                );
+        let Some(mut applicant_stats) =
+            UserStats::try_load(stats_account, &task_application.applicant, &coin_mint)
+        else {
+            skipped.push(application_account.key());
+            continue;
+        };
+        let Ok(payout) = milestone_payout(task_info, &task_application, task_fee_bps, now) else {
+            skipped.push(application_account.key());
+            continue;
+        };
+        let MilestonePayout {
+            task_fee,
+            task_amount_after_fee,
+            requester_refund,
+            is_final_payout,
+            ..
+        } = payout;
+        let pays_rewards = is_final_payout && task_info.rewards > 0;
+        if !is_applicant_account || (pays_rewards && task_application.inviter != Pubkey::default())
+        {
+            skipped.push(application_account.key());
+            continue;
+        }
+
+        // Without an inviter the whole rewards go to the treasury, as in `withdraw`
+        let rewards_fee = if pays_rewards { task_info.rewards } else { 0 };
+        let total_fee = task_fee
+            .checked_add(rewards_fee)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+
+        let payouts = [
+            (ctx.accounts.treasury_vault.to_account_info(), total_fee),
+            (applicant_coin_account.clone(), task_amount_after_fee),
+            (
+                ctx.accounts.requester_coin_account.to_account_info(),
+                requester_refund,
+            ),
+        ];
+        for (destination, amount) in payouts {
+            if amount == 0 {
+                continue;
+            }
+            token_utils::transfer_token_with_singer(
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.task_vault.to_account_info(),
+                ctx.accounts.coin_mint.to_account_info(),
+                destination,
+                ctx.accounts.vault_authority.to_account_info(),
+                &[],
+                amount,
+                ctx.accounts.coin_mint.decimals,
+                Some(&[seeds]),
+            )?;
+        }
+
+        if total_fee > 0 {
+            ctx.accounts
+                .treasury
+                .accrue(token_utils::net_of_transfer_fee(
+                    &ctx.accounts.coin_mint.to_account_info(),
+                    total_fee,
+                )?)?;
+        }
+
+        let released_amount = total_fee
+            .checked_add(task_amount_after_fee)
+            .and_then(|amount| amount.checked_add(requester_refund))
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        let task_info = &mut ctx.accounts.task_info;
+        record_withdrawal(task_info, &mut task_application, released_amount, &payout)?;
+        applicant_stats.record_payout(task_amount_after_fee, is_final_payout)?;
+        task_application.store(application_account)?;
+        applicant_stats.store(stats_account)?;
+        processed += 1;
+
+        emit_cpi!(RewardWithdrawn {
+            task_info: task_info.key(),
+            task_id: task_info.task_id,
+            applicant: task_application.applicant,
+            coin_mint,
+            task_amount: task_amount_after_fee,
+            task_fee,
+            inviter: None,
+            inviter_rewards: 0,
+            level_rewards: Vec::new(),
+            rewards_fee,
+            paid_milestones: task_application.paid_milestones,
+            requester_refund,
+            claimed_amount: task_application.claimed_amount,
+        });
+    }
+    let task_info = &ctx.accounts.task_info;
+    emit_cpi!(ApplicationsBatchProcessed {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        processed,
+        skipped,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use crate::{
+    errors::TaskTraderError,
+    events::{ApplicationVerified, ApplicationsBatchProcessed},
+    instructions::verify_task_application::{review_application, ApplicationReview},
+    state::admin::Admin,
+    state::task_application::TaskApplication,
+    state::task_info::{TaskInfo, TaskState},
+    state::user_stats::UserStats,
+};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BatchVerifyApplications<'info> {
+    #[account(
+        mut,
+        constraint = task_info.is_authorized_reviewer(&user.key(), &admin.signer) @ TaskTraderError::InvalidReviewer,
+        constraint = matches!(task_info.state, TaskState::Open | TaskState::Closed) @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin: Account<'info, Admin>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+/// Reviews many applications of one task at once. `remaining_accounts` holds
+/// a `(TaskApplication, applicant UserStats)` pair per entry of `reviews`, in
+/// the same order. Entries that fail the checks of `verify_task_application`,
+/// or whose applicant has no stats account yet, are skipped and reported
+/// instead of failing the whole batch. Like there, a closed or expired task
+/// only takes milestone approvals of accepted applications.
+pub fn batch_verify_applications<'info>(
+    ctx: Context<'_, '_, '_, 'info, BatchVerifyApplications<'info>>,
+    reviews: Vec<ApplicationReview>,
+) -> Result<()> {
+    let entries = ctx.remaining_accounts.chunks_exact(2);
+    if !entries.remainder().is_empty() || entries.len() != reviews.len() {
+        return Err(TaskTraderError::InvalidBatch.into());
+    }
+
+    let task_info = &mut ctx.accounts.task_info;
+    let now = Clock::get()?.unix_timestamp;
+
+    let mut processed: u32 = 0;
+    let mut skipped = Vec::new();
+    for (entry, review) in entries.zip(reviews.iter()) {
+        let (account, stats_account) = (&entry[0], &entry[1]);
+        let Some(mut task_application) = TaskApplication::try_load(account, task_info.task_id)
+        else {
+            skipped.push(account.key());
+            continue;
+        };
+        let Some(mut applicant_stats) = UserStats::try_load(
+            stats_account,
+            &task_application.applicant,
+            &task_info.coin_mint,
+        ) else {
+            skipped.push(account.key());
+            continue;
+        };
+        let previous_state = task_application.state;
+        if review_application(task_info, &mut task_application, review, now).is_err() {
+            skipped.push(account.key());
+            continue;
+        }
+        applicant_stats.record_review(previous_state, task_application.state)?;
+        task_application.store(account)?;
+        applicant_stats.store(stats_account)?;
+        processed += 1;
+
+        emit_cpi!(ApplicationVerified {
+            task_info: task_info.key(),
+            task_id: task_info.task_id,
+            applicant: task_application.applicant,
+            reviewer: ctx.accounts.user.key(),
+            is_accepted: review.is_accepted,
+            rejection_reason: task_application.rejection_reason,
+            approved_milestones: task_application.approved_milestones,
+            awarded_amount: task_application.awarded_amount,
+        });
+    }
+
+    emit_cpi!(ApplicationsBatchProcessed {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        processed,
+        skipped,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use crate::{
+    errors::TaskTraderError,
+    events::ApplicationCancelled,
+    state::{
+        task_application::{ApplicationState, TaskApplication},
+        task_info::TaskInfo,
+    },
+};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelApplication<'info> {
+    #[account(mut)]
+    pub applicant: Signer<'info>,
+
+    #[account(mut)]
+    pub task_info: Account<'info, TaskInfo>,
+
+    #[account(
+        mut,
+        close = applicant,
+        seeds = [
+            b"task_application",
+            task_info.key().as_ref(),
+            applicant.key().as_ref(),
+        ],
+        bump,
+        constraint = matches!(
+            task_application.state,
+            ApplicationState::Applied | ApplicationState::Submitted
+        ) @ TaskTraderError::InvalidApplicationState,
+    )]
+    pub task_application: Account<'info, TaskApplication>,
+}
+
+/// Withdraws an application that has not been reviewed yet and returns its rent.
+pub fn cancel_application(ctx: Context<CancelApplication>) -> Result<()> {
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.revert_application()?;
+    task_info.record_disputable(ctx.accounts.task_application.is_disputable(), false)?;
+
+    emit_cpi!(ApplicationCancelled {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        applicant: ctx.accounts.applicant.key(),
+    });
+
+    Ok(())
+}
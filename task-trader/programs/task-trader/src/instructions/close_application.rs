@@ -0,0 +1,51 @@
+use crate::{
+    errors::TaskTraderError,
+    events::ApplicationClosed,
+    state::{
+        task_application::{ApplicationState, TaskApplication},
+        task_info::TaskInfo,
+    },
+};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseApplication<'info> {
+    #[account(mut)]
+    pub applicant: Signer<'info>,
+
+    #[account(mut)]
+    pub task_info: Account<'info, TaskInfo>,
+
+    #[account(
+        mut,
+        close = applicant,
+        seeds = [
+            b"task_application",
+            task_info.key().as_ref(),
+            applicant.key().as_ref(),
+        ],
+        bump,
+        constraint = matches!(
+            task_application.state,
+            ApplicationState::Withdrawed | ApplicationState::Rejected
+        ) @ TaskTraderError::InvalidApplicationState,
+    )]
+    pub task_application: Account<'info, TaskApplication>,
+}
+
+/// Reclaims the rent of an application that has been paid out or rejected.
+/// Closing rejected work gives up its dispute.
+pub fn close_application(ctx: Context<CloseApplication>) -> Result<()> {
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.revert_application()?;
+    task_info.record_disputable(ctx.accounts.task_application.is_disputable(), false)?;
+
+    emit_cpi!(ApplicationClosed {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        applicant: ctx.accounts.applicant.key(),
+    });
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use crate::{
+    errors::TaskTraderError,
+    events::TaskClosed,
+    state::task_info::{TaskInfo, TaskState},
+    utils::token_utils,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseTask<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = task_info.requester == user.key() @ TaskTraderError::InvalidRequester,
+        constraint = task_info.is_active() @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+
+    /// CHECK: This is not dangerous
+    #[account(
+        seeds = [b"vault_authority", task_info.key().as_ref()],
+        bump,
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        constraint = coin_mint.key() == task_info.coin_mint @ TaskTraderError::InvalidMint,
+        mint::token_program = token_program,
+    )]
+    pub coin_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = coin_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_coin_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"task_vault", task_info.key().as_ref()],
+        bump,
+        constraint = task_vault.amount >= task_info.escrow_amount @ TaskTraderError::InvalidPoolAccount,
+    )]
+    pub task_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Closes the task and refunds the escrow of seats that were never filled.
+/// Accepted applicants keep their reserved share and can still withdraw, and
+/// their remaining milestones can still be approved. Seats that applicants
+/// with submitted work may still dispute stay escrowed until the task is reclaimed.
+pub fn close_task<'info>(mut ctx: Context<'_, '_, '_, 'info, CloseTask<'info>>) -> Result<()> {
+    msg!("Closing task...");
+
+    let disputable_count = ctx.accounts.task_info.disputable_count;
+    let refund_amount = refund_unclaimed(&mut ctx, disputable_count)?;
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.state = TaskState::Closed;
+
+    emit_cpi!(TaskClosed {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        state: TaskState::Closed,
+        refund_amount,
+    });
+
+    Ok(())
+}
+
+/// Cancels a task that has no accepted application and no submitted work
+/// awaiting a decision, and refunds the whole escrow. Rejected work stops
+/// counting once its dispute window lapsed, see `lapse_dispute`.
+pub fn cancel_task<'info>(mut ctx: Context<'_, '_, '_, 'info, CloseTask<'info>>) -> Result<()> {
+    msg!("Cancelling task...");
+
+    let task_info = &ctx.accounts.task_info;
+    if task_info.accepted_count > 0 || task_info.disputable_count > 0 {
+        return Err(TaskTraderError::InvalidTaskState.into());
+    }
+
+    let refund_amount = refund_unclaimed(&mut ctx, 0)?;
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.state = TaskState::Cancelled;
+
+    emit_cpi!(TaskClosed {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        state: TaskState::Cancelled,
+        refund_amount,
+    });
+
+    Ok(())
+}
+
+/// Refunds every seat nobody was accepted for except `kept_seats` of them.
+fn refund_unclaimed<'info>(
+    ctx: &mut Context<'_, '_, '_, 'info, CloseTask<'info>>,
+    kept_seats: u64,
+) -> Result<u64> {
+    let refund_amount = ctx.accounts.task_info.release_open_seats(kept_seats)?;
+    if refund_amount == 0 {
+        return Ok(0);
+    }
+
+    let task_info_key = ctx.accounts.task_info.key();
+    let seeds = &[
+        b"vault_authority".as_ref(),
+        task_info_key.as_ref(),
+        &[ctx.bumps.vault_authority],
+    ];
+    token_utils::transfer_token_with_singer(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.task_vault.to_account_info(),
+        ctx.accounts.coin_mint.to_account_info(),
+        ctx.accounts.user_coin_account.to_account_info(),
+        ctx.accounts.vault_authority.to_account_info(),
+        ctx.remaining_accounts,
+        refund_amount,
+        ctx.accounts.coin_mint.decimals,
+        Some(&[seeds]),
+    )?;
+
+    if token_utils::is_native_mint(&ctx.accounts.coin_mint.key()) {
+        token_utils::unwrap_sol(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_coin_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+        )?;
+    }
+
+    ctx.accounts.task_info.release_escrow(refund_amount)?;
+
+    Ok(refund_amount)
+}
@@ -0,0 +1,86 @@
+use crate::{
+    errors::TaskTraderError,
+    events::FeesCollected,
+    state::{admin::Admin, treasury::Treasury},
+    utils::token_utils,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.signer == *payer.key @ TaskTraderError::Unauthorized
+    )]
+    pub admin: Account<'info, Admin>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub coin_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", coin_mint.key().as_ref()],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault", coin_mint.key().as_ref()],
+        bump,
+        constraint = treasury_vault.amount >= treasury.accrued_fees @ TaskTraderError::InvalidPoolAccount,
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == coin_mint.key() @ TaskTraderError::InvalidMint,
+    )]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Sweeps every accrued fee of the mint from the treasury to `destination`.
+pub fn collect_fees<'info>(ctx: Context<'_, '_, '_, 'info, CollectFees<'info>>) -> Result<()> {
+    msg!("Collecting fees...");
+
+    let amount = ctx.accounts.treasury.collect()?;
+    if amount == 0 {
+        return Err(TaskTraderError::InvalidAmount.into());
+    }
+
+    let coin_mint = ctx.accounts.coin_mint.key();
+    let seeds = &[
+        b"treasury".as_ref(),
+        coin_mint.as_ref(),
+        &[ctx.accounts.treasury.bump],
+    ];
+    token_utils::transfer_token_with_singer(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.treasury_vault.to_account_info(),
+        ctx.accounts.coin_mint.to_account_info(),
+        ctx.accounts.destination.to_account_info(),
+        ctx.accounts.treasury.to_account_info(),
+        ctx.remaining_accounts,
+        amount,
+        ctx.accounts.coin_mint.decimals,
+        Some(&[seeds]),
+    )?;
+
+    emit_cpi!(FeesCollected {
+        coin_mint,
+        destination: ctx.accounts.destination.key(),
+        amount,
+        collected_fees: ctx.accounts.treasury.collected_fees,
+    });
+
+    Ok(())
+}
@@ -2,18 +2,26 @@ use {
     anchor_lang::prelude::*,
     anchor_spl::{
         associated_token::AssociatedToken,
-        token::{Mint, Token, TokenAccount},
+        token_interface::{Mint, TokenAccount, TokenInterface},
     },
 };
 
 use crate::{
     errors::TaskTraderError,
-    state::{admin::Admin, support_coin::SupportCoin, task_info::TaskInfo},
+    events::TaskCreated,
+    state::{
+        admin::Admin,
+        support_coin::SupportCoin,
+        task_counter::TaskCounter,
+        task_info::{SbtGate, TaskInfo, TaskMetadata, TaskState, Vesting},
+        treasury::Treasury,
+        user_stats::UserStats,
+    },
     utils::token_utils,
 };
 
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(task_id: u64)]
 pub struct CreateTask<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
@@ -24,40 +32,62 @@ pub struct CreateTask<'info> {
     )]
     pub admin: Account<'info, Admin>,
 
-    /// CHECK: This is not dangerous
     #[account(
-        seeds = [b"pool_authority"],
-        bump,
-        constraint = pool_authority.key() == Pubkey::find_program_address(&[b"pool_authority"], &crate::ID).0
+        mut,
+        seeds = [b"task_counter"],
+        bump
     )]
-    pub pool_authority: AccountInfo<'info>,
+    pub task_counter: Account<'info, TaskCounter>,
 
     #[account(
         init,
         payer = user,
         space = TaskInfo::INIT_SPACE,
-        seeds = [b"task_info", task_id.to_le_bytes().as_ref()],
+        seeds = [b"task_info", task_counter.next_task_id.to_le_bytes().as_ref()],
         bump
     )]
     pub task_info: Account<'info, TaskInfo>,
 
-    pub coin_mint: Account<'info, Mint>,
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub coin_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = coin_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_coin_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: This is not dangerous
+    #[account(
+        seeds = [b"vault_authority", task_info.key().as_ref()],
+        bump,
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"task_vault", task_info.key().as_ref()],
+        bump,
+        token::mint = coin_mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
     )]
-    pub user_coin_account: Box<Account<'info, TokenAccount>>,
+    pub task_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
         payer = user,
-        associated_token::mint = coin_mint,
-        associated_token::authority = pool_authority,
+        space = UserStats::INIT_SPACE,
+        seeds = [b"user_stats", user.key().as_ref(), coin_mint.key().as_ref()],
+        bump
     )]
-    pub pool_coin_account: Box<Account<'info, TokenAccount>>,
+    pub requester_stats: Box<Account<'info, UserStats>>,
 
     #[account(
         seeds = [b"support_coin"],
@@ -65,50 +95,54 @@ pub struct CreateTask<'info> {
     )]
     pub support_coin: Account<'info, SupportCoin>,
 
-    /// CHECK: This is not dangerous
+    /// Payouts settle their fees here, so tasks can only use a mint whose
+    /// treasury was initialized.
     #[account(
-        constraint = fee_receiver.key() == admin.fee_receiver @ TaskTraderError::Unauthorized
-    )]
-    pub fee_receiver: AccountInfo<'info>,
-
-    #[account(
-        init_if_needed,
-        payer = user,
-        associated_token::mint = coin_mint,
-        associated_token::authority = fee_receiver,
+        seeds = [b"treasury", coin_mint.key().as_ref()],
+        bump = treasury.bump,
     )]
-    pub fee_receiver_coin_account: Box<Account<'info, TokenAccount>>,
+    pub treasury: Box<Account<'info, Treasury>>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
-pub fn create_task(
-    ctx: Context<CreateTask>,
-    task_id: u64,
+/// Creates a task under the next ID handed out by the task counter and
+/// returns that ID.
+#[allow(clippy::too_many_arguments)]
+pub fn create_task<'info>(
+    ctx: Context<'_, '_, '_, 'info, CreateTask<'info>>,
     task_amount: u64,
     taker_num: u64,
-    coin_mint: Pubkey,
     rewards: u64,
-) -> Result<()> {
+    apply_deadline: i64,
+    complete_deadline: i64,
+    allowed_applicants: Vec<Pubkey>,
+    allowlist_root: Option<[u8; 32]>,
+    milestones: Vec<u64>,
+    sbt_gate: Option<SbtGate>,
+    vesting: Option<Vesting>,
+    metadata: TaskMetadata,
+) -> Result<u64> {
     msg!("Creating task...");
 
     if task_amount == 0 || taker_num == 0 {
         return Err(TaskTraderError::InvalidAmount.into());
     }
+    let now = Clock::get()?.unix_timestamp;
+    if apply_deadline <= now || complete_deadline < apply_deadline {
+        return Err(TaskTraderError::InvalidExpireTime.into());
+    }
+    // The escrow is held in the mint account passed in, so that is the task's coin
+    let coin_mint = ctx.accounts.coin_mint.key();
     if !ctx.accounts.support_coin.coin_mints.contains(&coin_mint) {
         return Err(TaskTraderError::InvalidCoinMint.into());
     }
 
-    token_utils::transfer_token(
-        ctx.accounts.token_program.to_account_info(),
-        ctx.accounts.user_coin_account.to_account_info(),
-        ctx.accounts.pool_coin_account.to_account_info(),
-        ctx.accounts.user.to_account_info(),
-        (task_amount + rewards) * taker_num,
-    )?;
+    // The task info address was derived from the current counter value
+    let task_id = ctx.accounts.task_counter.allocate()?;
 
     // Initialize task info
     let task_info = &mut ctx.accounts.task_info;
@@ -118,6 +152,61 @@ pub fn create_task(
     task_info.coin_mint = coin_mint;
     task_info.rewards = rewards;
     task_info.requester = ctx.accounts.user.key();
-
-    Ok(())
+    task_info.state = TaskState::Open;
+    task_info.applied_count = 0;
+    task_info.accepted_count = 0;
+    task_info.paid_count = 0;
+    task_info.apply_deadline = apply_deadline;
+    task_info.complete_deadline = complete_deadline;
+    task_info.set_allowlist(allowed_applicants, allowlist_root)?;
+    task_info.set_milestones(milestones)?;
+    task_info.sbt_gate = sbt_gate;
+    task_info.set_vesting(vesting)?;
+    task_info.set_metadata(metadata)?;
+
+    let deposit_amount = task_info
+        .amount_per_taker()?
+        .checked_mul(taker_num)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+
+    // Escrow what the vault actually received
+    let received_amount = token_utils::deposit_to_vault(
+        ctx.accounts.system_program.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.coin_mint,
+        ctx.accounts.user.to_account_info(),
+        &ctx.accounts.user_coin_account,
+        &mut ctx.accounts.task_vault,
+        ctx.remaining_accounts,
+        deposit_amount,
+    )?;
+    ctx.accounts.task_info.escrow_amount = received_amount;
+
+    let requester_stats = &mut ctx.accounts.requester_stats;
+    requester_stats.initialize(
+        ctx.accounts.user.key(),
+        coin_mint,
+        ctx.bumps.requester_stats,
+    );
+    requester_stats.record_task_posted(received_amount)?;
+
+    let task_info = &ctx.accounts.task_info;
+    emit_cpi!(TaskCreated {
+        task_info: task_info.key(),
+        task_id,
+        requester: ctx.accounts.user.key(),
+        coin_mint,
+        task_amount,
+        taker_num,
+        rewards,
+        escrow_amount: received_amount,
+        apply_deadline,
+        complete_deadline,
+        milestones: task_info.milestones.clone(),
+        sbt_gate,
+        vesting,
+        metadata: task_info.metadata.clone(),
+    });
+
+    Ok(task_id)
 }
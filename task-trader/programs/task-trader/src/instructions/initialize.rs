@@ -1,5 +1,6 @@
-use crate::state::admin::Admin;
+use crate::{events::AdminUpdated, state::admin::Admin, utils::fee_utils};
 use anchor_lang::prelude::*;
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(mut)]
@@ -17,12 +18,30 @@ pub struct Initialize<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
-pub fn initialize(ctx: Context<Initialize>, signer: Pubkey, fee_receiver: Pubkey, fee_ratio: u64) -> Result<()> {
+pub fn initialize(
+    ctx: Context<Initialize>,
+    signer: Pubkey,
+    task_fee_bps: u16,
+    reward_fee_bps: u16,
+    arbiter: Pubkey,
+) -> Result<()> {
     msg!("Initalizing...");
 
+    fee_utils::validate_fee_bps(task_fee_bps)?;
+    fee_utils::validate_fee_bps(reward_fee_bps)?;
+
     ctx.accounts.admin.signer = signer;
-    ctx.accounts.admin.fee_receiver = fee_receiver;
-    ctx.accounts.admin.fee_ratio = fee_ratio;
+    ctx.accounts.admin.task_fee_bps = task_fee_bps;
+    ctx.accounts.admin.reward_fee_bps = reward_fee_bps;
+    ctx.accounts.admin.arbiter = arbiter;
+    ctx.accounts.admin.referral_splits_bps = Admin::DEFAULT_REFERRAL_SPLITS_BPS;
+
+    emit_cpi!(AdminUpdated {
+        signer,
+        task_fee_bps,
+        reward_fee_bps,
+        arbiter,
+    });
 
     Ok(())
 }
@@ -0,0 +1,45 @@
+use crate::{
+    errors::TaskTraderError, events::TaskCounterInitialized, state::admin::Admin,
+    state::task_counter::TaskCounter,
+};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitializeTaskCounter<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.signer == *payer.key @ TaskTraderError::Unauthorized
+    )]
+    pub admin: Account<'info, Admin>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = TaskCounter::INIT_SPACE,
+        seeds = [b"task_counter"],
+        bump
+    )]
+    pub task_counter: Account<'info, TaskCounter>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates the task ID allocator. Deployments that already hold tasks with
+/// client-chosen IDs start the counter past the highest one in use.
+pub fn initialize_task_counter(
+    ctx: Context<InitializeTaskCounter>,
+    next_task_id: u64,
+) -> Result<()> {
+    msg!("Initializing task counter...");
+
+    ctx.accounts.task_counter.next_task_id = next_task_id;
+
+    emit_cpi!(TaskCounterInitialized { next_task_id });
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::{
+    errors::TaskTraderError, events::TreasuryInitialized, state::admin::Admin,
+    state::treasury::Treasury,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.signer == *payer.key @ TaskTraderError::Unauthorized
+    )]
+    pub admin: Account<'info, Admin>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub coin_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Treasury::INIT_SPACE,
+        seeds = [b"treasury", coin_mint.key().as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"treasury_vault", coin_mint.key().as_ref()],
+        bump,
+        token::mint = coin_mint,
+        token::authority = treasury,
+        token::token_program = token_program,
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates the fee treasury of a mint. Tasks in that mint cannot pay out
+/// until it exists.
+pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+    msg!("Initializing treasury...");
+
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.coin_mint = ctx.accounts.coin_mint.key();
+    treasury.accrued_fees = 0;
+    treasury.collected_fees = 0;
+    treasury.bump = ctx.bumps.treasury;
+
+    emit_cpi!(TreasuryInitialized {
+        coin_mint: treasury.coin_mint,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::{
+    errors::TaskTraderError,
+    events::DisputeLapsed,
+    state::{task_application::TaskApplication, task_info::TaskInfo},
+};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct LapseDispute<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(mut)]
+    pub task_info: Account<'info, TaskInfo>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"task_application",
+            task_info.key().as_ref(),
+            task_application.applicant.as_ref(),
+        ],
+        bump,
+    )]
+    pub task_application: Account<'info, TaskApplication>,
+}
+
+/// Ends the dispute right of rejected work whose dispute window has passed,
+/// so its seat no longer blocks cancelling or shrinking the task. Anyone may
+/// call it.
+pub fn lapse_dispute(ctx: Context<LapseDispute>) -> Result<()> {
+    let task_application = &mut ctx.accounts.task_application;
+    if !task_application.is_dispute_lapsed(Clock::get()?.unix_timestamp) {
+        return Err(TaskTraderError::DisputeWindowOpen.into());
+    }
+    task_application.dispute_deadline = 0;
+
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.record_disputable(true, false)?;
+
+    emit_cpi!(DisputeLapsed {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        applicant: task_application.applicant,
+    });
+
+    Ok(())
+}
@@ -18,3 +18,69 @@ pub use withdraw::*;
 
 pub mod update_task_support_coin;
 pub use update_task_support_coin::*;
+
+pub mod update_coin_fee_override;
+pub use update_coin_fee_override::*;
+
+pub mod update_task_state;
+pub use update_task_state::*;
+
+pub mod close_task;
+pub use close_task::*;
+
+pub mod reclaim_expired_task;
+pub use reclaim_expired_task::*;
+
+pub mod update_task_allowlist;
+pub use update_task_allowlist::*;
+
+pub mod update_task_reviewers;
+pub use update_task_reviewers::*;
+
+pub mod cancel_application;
+pub use cancel_application::*;
+
+pub mod close_application;
+pub use close_application::*;
+
+pub mod raise_dispute;
+pub use raise_dispute::*;
+
+pub mod resolve_dispute;
+pub use resolve_dispute::*;
+
+pub mod register_referral;
+pub use register_referral::*;
+
+pub mod update_referral_splits;
+pub use update_referral_splits::*;
+
+pub mod adjust_task;
+pub use adjust_task::*;
+
+pub mod initialize_task_counter;
+pub use initialize_task_counter::*;
+
+pub mod batch_verify_applications;
+pub use batch_verify_applications::*;
+
+pub mod batch_payout;
+pub use batch_payout::*;
+
+pub mod initialize_treasury;
+pub use initialize_treasury::*;
+
+pub mod collect_fees;
+pub use collect_fees::*;
+
+pub mod accept_with_attestation;
+pub use accept_with_attestation::*;
+
+pub mod submit_work;
+pub use submit_work::*;
+
+pub mod update_task_metadata;
+pub use update_task_metadata::*;
+
+pub mod lapse_dispute;
+pub use lapse_dispute::*;
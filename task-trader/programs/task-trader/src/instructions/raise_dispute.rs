@@ -0,0 +1,62 @@
+use crate::{
+    errors::TaskTraderError,
+    events::DisputeRaised,
+    state::{
+        task_application::{ApplicationState, TaskApplication},
+        task_info::{TaskInfo, TaskState},
+    },
+};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RaiseDispute<'info> {
+    pub applicant: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = task_info.state != TaskState::Cancelled @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"task_application",
+            task_info.key().as_ref(),
+            applicant.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub task_application: Account<'info, TaskApplication>,
+}
+
+/// Escalates submitted work the requester has not accepted to the arbiter,
+/// also after the task was closed, until the task is reclaimed. Rejected work
+/// must be disputed within `TaskApplication::DISPUTE_WINDOW`.
+/// The applicant's share is reserved like an accepted seat, so closing or
+/// reclaiming the task cannot refund it while the dispute is open.
+pub fn raise_dispute(ctx: Context<RaiseDispute>) -> Result<()> {
+    if !ctx
+        .accounts
+        .task_application
+        .can_dispute(Clock::get()?.unix_timestamp)
+    {
+        return Err(TaskTraderError::InvalidApplicationState.into());
+    }
+
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.record_acceptance()?;
+    task_info.record_disputable(true, false)?;
+
+    let task_application = &mut ctx.accounts.task_application;
+    task_application.state = ApplicationState::Disputed;
+
+    emit_cpi!(DisputeRaised {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        applicant: task_application.applicant,
+    });
+
+    Ok(())
+}
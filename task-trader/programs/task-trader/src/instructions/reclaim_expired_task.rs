@@ -0,0 +1,114 @@
+use crate::{
+    errors::TaskTraderError,
+    events::TaskClosed,
+    state::task_info::{TaskInfo, TaskState},
+    utils::token_utils,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReclaimExpiredTask<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = task_info.state != TaskState::Cancelled @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+
+    /// CHECK: This is not dangerous
+    #[account(
+        constraint = requester.key() == task_info.requester @ TaskTraderError::InvalidRequester
+    )]
+    pub requester: AccountInfo<'info>,
+
+    /// CHECK: This is not dangerous
+    #[account(
+        seeds = [b"vault_authority", task_info.key().as_ref()],
+        bump,
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        constraint = coin_mint.key() == task_info.coin_mint @ TaskTraderError::InvalidMint,
+        mint::token_program = token_program,
+    )]
+    pub coin_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = coin_mint,
+        associated_token::authority = requester,
+        associated_token::token_program = token_program,
+    )]
+    pub requester_coin_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"task_vault", task_info.key().as_ref()],
+        bump,
+        constraint = task_vault.amount >= task_info.escrow_amount @ TaskTraderError::InvalidPoolAccount,
+    )]
+    pub task_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Permissionless crank that closes a task once its completion deadline has
+/// passed and returns the unclaimed escrow to the requester. This also ends
+/// the seats a closed task kept for disputes.
+pub fn reclaim_expired_task<'info>(
+    ctx: Context<'_, '_, '_, 'info, ReclaimExpiredTask<'info>>,
+) -> Result<()> {
+    if !ctx
+        .accounts
+        .task_info
+        .is_expired(Clock::get()?.unix_timestamp)
+    {
+        return Err(TaskTraderError::TaskNotExpired.into());
+    }
+
+    let refund_amount = ctx.accounts.task_info.release_open_seats(0)?;
+    let task_info = &ctx.accounts.task_info;
+    if refund_amount > 0 {
+        let task_info_key = task_info.key();
+        let seeds = &[
+            b"vault_authority".as_ref(),
+            task_info_key.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        token_utils::transfer_token_with_singer(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.task_vault.to_account_info(),
+            ctx.accounts.coin_mint.to_account_info(),
+            ctx.accounts.requester_coin_account.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            ctx.remaining_accounts,
+            refund_amount,
+            ctx.accounts.coin_mint.decimals,
+            Some(&[seeds]),
+        )?;
+    }
+
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.release_escrow(refund_amount)?;
+    task_info.state = TaskState::Closed;
+
+    emit_cpi!(TaskClosed {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        state: TaskState::Closed,
+        refund_amount,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::{errors::TaskTraderError, events::ReferralRegistered, state::referral::Referral};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RegisterReferral<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        space = Referral::INIT_SPACE,
+        seeds = [b"referral", user.key().as_ref()],
+        bump
+    )]
+    pub referral: Account<'info, Referral>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Records who invited the signer. A user's inviter can only be set once.
+pub fn register_referral(ctx: Context<RegisterReferral>, inviter: Pubkey) -> Result<()> {
+    let user = ctx.accounts.user.key();
+    if inviter == user || inviter == Pubkey::default() {
+        return Err(TaskTraderError::InvalidInviter.into());
+    }
+
+    let referral = &mut ctx.accounts.referral;
+    referral.user = user;
+    referral.inviter = inviter;
+
+    emit_cpi!(ReferralRegistered { user, inviter });
+
+    Ok(())
+}
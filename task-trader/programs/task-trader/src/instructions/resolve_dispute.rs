@@ -0,0 +1,225 @@
+use crate::{
+    errors::TaskTraderError,
+    events::DisputeResolved,
+    state::{
+        admin::Admin,
+        support_coin::SupportCoin,
+        task_application::{ApplicationState, TaskApplication},
+        task_info::TaskInfo,
+        treasury::Treasury,
+        user_stats::UserStats,
+    },
+    utils::{fee_utils, token_utils},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(mut)]
+    pub arbiter: Signer<'info>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.arbiter == arbiter.key() @ TaskTraderError::InvalidArbiter,
+    )]
+    pub admin: Account<'info, Admin>,
+
+    #[account(
+        seeds = [b"support_coin"],
+        bump,
+    )]
+    pub support_coin: Account<'info, SupportCoin>,
+
+    #[account(mut)]
+    pub task_info: Account<'info, TaskInfo>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"task_application",
+            task_info.key().as_ref(),
+            applicant.key().as_ref(),
+        ],
+        bump,
+        constraint = task_application.state == ApplicationState::Disputed @ TaskTraderError::InvalidApplicationState,
+    )]
+    pub task_application: Account<'info, TaskApplication>,
+
+    /// CHECK: This is not dangerous
+    pub applicant: AccountInfo<'info>,
+
+    /// CHECK: This is not dangerous
+    #[account(
+        constraint = requester.key() == task_info.requester @ TaskTraderError::InvalidRequester
+    )]
+    pub requester: AccountInfo<'info>,
+
+    /// CHECK: This is not dangerous
+    #[account(
+        seeds = [b"vault_authority", task_info.key().as_ref()],
+        bump,
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        constraint = coin_mint.key() == task_info.coin_mint @ TaskTraderError::InvalidMint,
+        mint::token_program = token_program,
+    )]
+    pub coin_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        associated_token::mint = coin_mint,
+        associated_token::authority = applicant,
+        associated_token::token_program = token_program,
+    )]
+    pub applicant_coin_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        associated_token::mint = coin_mint,
+        associated_token::authority = requester,
+        associated_token::token_program = token_program,
+    )]
+    pub requester_coin_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"task_vault", task_info.key().as_ref()],
+        bump,
+        constraint = task_vault.amount >= task_info.escrow_amount @ TaskTraderError::InvalidPoolAccount,
+    )]
+    pub task_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", coin_mint.key().as_ref()],
+        bump = treasury.bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault", coin_mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        space = UserStats::INIT_SPACE,
+        seeds = [b"user_stats", applicant.key().as_ref(), coin_mint.key().as_ref()],
+        bump
+    )]
+    pub applicant_stats: Box<Account<'info, UserStats>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Settles a disputed application from the task vault. The applicant receives
+/// `applicant_amount` of the task amount, less the usual task fee, which goes to the treasury; the rest of
+/// the frozen share, including the inviter rewards, goes back to the requester.
+/// Any amount awarded to the applicant is recorded as a completed task.
+pub fn resolve_dispute<'info>(
+    ctx: Context<'_, '_, '_, 'info, ResolveDispute<'info>>,
+    applicant_amount: u64,
+) -> Result<()> {
+    let task_info = &ctx.accounts.task_info;
+    if applicant_amount > task_info.task_amount {
+        return Err(TaskTraderError::InvalidAmount.into());
+    }
+
+    let (task_fee_bps, _) = ctx
+        .accounts
+        .admin
+        .fee_rates(&ctx.accounts.support_coin, &task_info.coin_mint);
+    let (task_fee, applicant_amount_after_fee) =
+        fee_utils::split_fee(applicant_amount, task_fee_bps)?;
+    let requester_amount = task_info
+        .amount_per_taker()?
+        .checked_sub(applicant_amount)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+
+    let task_info_key = task_info.key();
+    let seeds = &[
+        b"vault_authority".as_ref(),
+        task_info_key.as_ref(),
+        &[ctx.bumps.vault_authority],
+    ];
+    let payouts = [
+        (&ctx.accounts.treasury_vault, task_fee),
+        (
+            &ctx.accounts.applicant_coin_account,
+            applicant_amount_after_fee,
+        ),
+        (&ctx.accounts.requester_coin_account, requester_amount),
+    ];
+    for (destination, amount) in payouts {
+        if amount == 0 {
+            continue;
+        }
+        token_utils::transfer_token_with_singer(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.task_vault.to_account_info(),
+            ctx.accounts.coin_mint.to_account_info(),
+            destination.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            ctx.accounts.coin_mint.decimals,
+            Some(&[seeds]),
+        )?;
+    }
+
+    if task_fee > 0 {
+        ctx.accounts
+            .treasury
+            .accrue(token_utils::net_of_transfer_fee(
+                &ctx.accounts.coin_mint.to_account_info(),
+                task_fee,
+            )?)?;
+    }
+
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.release_escrow(
+        applicant_amount
+            .checked_add(requester_amount)
+            .ok_or(TaskTraderError::NumericalOverflow)?,
+    )?;
+    task_info.record_payout()?;
+
+    // The frozen share is fully settled, so the application counts as paid out
+    let task_application = &mut ctx.accounts.task_application;
+    task_application.state = ApplicationState::Withdrawed;
+
+    let applicant_stats = &mut ctx.accounts.applicant_stats;
+    applicant_stats.initialize(
+        task_application.applicant,
+        task_info.coin_mint,
+        ctx.bumps.applicant_stats,
+    );
+    applicant_stats.record_payout(applicant_amount_after_fee, applicant_amount > 0)?;
+
+    emit_cpi!(DisputeResolved {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        applicant: task_application.applicant,
+        arbiter: ctx.accounts.arbiter.key(),
+        applicant_amount: applicant_amount_after_fee,
+        task_fee,
+        requester_amount,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::{
+    errors::TaskTraderError,
+    events::WorkSubmitted,
+    state::{
+        task_application::{ApplicationState, TaskApplication},
+        task_info::TaskInfo,
+        user_stats::UserStats,
+    },
+};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(work_hash: [u8; 32], work_uri: String)]
+pub struct SubmitWork<'info> {
+    #[account(mut)]
+    pub applicant: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = task_info.is_active() @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"task_application",
+            task_info.key().as_ref(),
+            applicant.key().as_ref(),
+        ],
+        bump,
+        constraint = !matches!(
+            task_application.state,
+            ApplicationState::Withdrawed | ApplicationState::Disputed
+        ) @ TaskTraderError::InvalidApplicationState,
+        realloc = TaskApplication::space(work_uri.len()),
+        realloc::payer = applicant,
+        realloc::zero = false,
+    )]
+    pub task_application: Account<'info, TaskApplication>,
+
+    #[account(
+        init_if_needed,
+        payer = applicant,
+        space = UserStats::INIT_SPACE,
+        seeds = [b"user_stats", applicant.key().as_ref(), task_info.coin_mint.as_ref()],
+        bump
+    )]
+    pub applicant_stats: Account<'info, UserStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Records the deliverable of an application for its reviewers. Pending and
+/// rejected applications move to `Submitted`; accepted ones keep their state
+/// and only update the deliverable, e.g. for the next milestone.
+pub fn submit_work(ctx: Context<SubmitWork>, work_hash: [u8; 32], work_uri: String) -> Result<()> {
+    if work_uri.is_empty() || work_uri.len() > TaskApplication::MAX_WORK_URI_LEN {
+        return Err(TaskTraderError::InvalidWorkUri.into());
+    }
+    let task_info = &mut ctx.accounts.task_info;
+    if task_info.is_expired(Clock::get()?.unix_timestamp) {
+        return Err(TaskTraderError::TaskExpired.into());
+    }
+
+    let task_application = &mut ctx.accounts.task_application;
+    let previous_state = task_application.state;
+    let was_disputable = task_application.is_disputable();
+    task_application.work_hash = work_hash;
+    task_application.work_uri = work_uri.clone();
+    if previous_state != ApplicationState::AcceptedByAcceptance {
+        task_application.state = ApplicationState::Submitted;
+        task_application.rejection_reason = None;
+    }
+    task_info.record_disputable(was_disputable, task_application.is_disputable())?;
+
+    // A resubmission withdraws a rejection from the applicant's record
+    let applicant_stats = &mut ctx.accounts.applicant_stats;
+    applicant_stats.initialize(
+        task_application.applicant,
+        task_info.coin_mint,
+        ctx.bumps.applicant_stats,
+    );
+    applicant_stats.record_review(previous_state, task_application.state)?;
+
+    emit_cpi!(WorkSubmitted {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        applicant: task_application.applicant,
+        work_hash,
+        work_uri,
+    });
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
-use crate::{errors::TaskTraderError, state::admin::Admin};
+use crate::{errors::TaskTraderError, events::AdminUpdated, state::admin::Admin, utils::fee_utils};
 use anchor_lang::prelude::*;
+#[event_cpi]
 #[derive(Accounts)]
 pub struct UpdateAdmin<'info> {
     #[account(mut)]
@@ -14,12 +15,29 @@ pub struct UpdateAdmin<'info> {
     pub admin: Account<'info, Admin>,
 }
 
-pub fn update_admin(ctx: Context<UpdateAdmin>, signer: Pubkey, fee_receiver: Pubkey, fee_ratio: u64) -> Result<()> {
+pub fn update_admin(
+    ctx: Context<UpdateAdmin>,
+    signer: Pubkey,
+    task_fee_bps: u16,
+    reward_fee_bps: u16,
+    arbiter: Pubkey,
+) -> Result<()> {
     msg!("Updating admin...");
 
+    fee_utils::validate_fee_bps(task_fee_bps)?;
+    fee_utils::validate_fee_bps(reward_fee_bps)?;
+
     ctx.accounts.admin.signer = signer;
-    ctx.accounts.admin.fee_receiver = fee_receiver;
-    ctx.accounts.admin.fee_ratio = fee_ratio;
-    
+    ctx.accounts.admin.task_fee_bps = task_fee_bps;
+    ctx.accounts.admin.reward_fee_bps = reward_fee_bps;
+    ctx.accounts.admin.arbiter = arbiter;
+
+    emit_cpi!(AdminUpdated {
+        signer,
+        task_fee_bps,
+        reward_fee_bps,
+        arbiter,
+    });
+
     Ok(())
 }
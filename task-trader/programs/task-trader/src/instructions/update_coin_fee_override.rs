@@ -0,0 +1,68 @@
+use crate::{
+    errors::TaskTraderError,
+    events::CoinFeeOverrideUpdated,
+    state::{
+        admin::Admin,
+        support_coin::{CoinFeeOverride, SupportCoin},
+    },
+    utils::fee_utils,
+};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateCoinFeeOverride<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.signer == *payer.key @ TaskTraderError::Unauthorized
+    )]
+    pub admin: Account<'info, Admin>,
+
+    #[account(
+        mut,
+        seeds = [b"support_coin"],
+        bump,
+    )]
+    pub support_coin: Account<'info, SupportCoin>,
+}
+
+/// Overrides the admin fee rates for a single supported coin. Rates left as
+/// `None` fall back to the admin defaults; clearing both removes the override.
+pub fn update_coin_fee_override(
+    ctx: Context<UpdateCoinFeeOverride>,
+    coin_mint: Pubkey,
+    task_fee_bps: Option<u16>,
+    reward_fee_bps: Option<u16>,
+) -> Result<()> {
+    msg!("Updating coin fee override...");
+
+    let support_coin = &mut ctx.accounts.support_coin;
+    if !support_coin.coin_mints.contains(&coin_mint) {
+        return Err(TaskTraderError::InvalidCoinMint.into());
+    }
+    for fee_bps in [task_fee_bps, reward_fee_bps].into_iter().flatten() {
+        fee_utils::validate_fee_bps(fee_bps)?;
+    }
+
+    support_coin
+        .fee_overrides
+        .retain(|fee_override| fee_override.coin_mint != coin_mint);
+    if task_fee_bps.is_some() || reward_fee_bps.is_some() {
+        support_coin.fee_overrides.push(CoinFeeOverride {
+            coin_mint,
+            task_fee_bps,
+            reward_fee_bps,
+        });
+    }
+
+    emit_cpi!(CoinFeeOverrideUpdated {
+        coin_mint,
+        task_fee_bps,
+        reward_fee_bps,
+    });
+
+    Ok(())
+}
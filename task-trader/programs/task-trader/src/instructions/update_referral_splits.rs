@@ -0,0 +1,36 @@
+use crate::{
+    errors::TaskTraderError, events::ReferralSplitsUpdated, state::admin::Admin, utils::fee_utils,
+};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateReferralSplits<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.signer == *payer.key @ TaskTraderError::Unauthorized
+    )]
+    pub admin: Account<'info, Admin>,
+}
+
+/// Sets the share of the task rewards paid to each referral level, starting
+/// with the direct inviter.
+pub fn update_referral_splits(
+    ctx: Context<UpdateReferralSplits>,
+    referral_splits_bps: [u16; Admin::MAX_REFERRAL_LEVELS],
+) -> Result<()> {
+    msg!("Updating referral splits...");
+
+    fee_utils::validate_referral_splits(&referral_splits_bps)?;
+    ctx.accounts.admin.referral_splits_bps = referral_splits_bps;
+
+    emit_cpi!(ReferralSplitsUpdated {
+        referral_splits_bps,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use crate::{errors::TaskTraderError, events::TaskAllowlistUpdated, state::task_info::TaskInfo};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateTaskAllowlist<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = task_info.requester == user.key() @ TaskTraderError::InvalidRequester,
+        constraint = task_info.is_active() @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+}
+
+pub fn update_task_allowlist(
+    ctx: Context<UpdateTaskAllowlist>,
+    allowed_applicants: Vec<Pubkey>,
+    allowlist_root: Option<[u8; 32]>,
+) -> Result<()> {
+    msg!("Updating task allowlist...");
+
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.set_allowlist(allowed_applicants.clone(), allowlist_root)?;
+
+    emit_cpi!(TaskAllowlistUpdated {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        allowed_applicants,
+        allowlist_root,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::{
+    errors::TaskTraderError,
+    events::TaskMetadataUpdated,
+    state::task_info::{TaskInfo, TaskMetadata, TaskState},
+};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateTaskMetadata<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = task_info.requester == user.key() @ TaskTraderError::InvalidRequester,
+        constraint = task_info.state == TaskState::Open @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+}
+
+/// Replaces the title, metadata URI, category and tags of an open task.
+pub fn update_task_metadata(
+    ctx: Context<UpdateTaskMetadata>,
+    metadata: TaskMetadata,
+) -> Result<()> {
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.set_metadata(metadata.clone())?;
+
+    emit_cpi!(TaskMetadataUpdated {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        metadata,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use crate::{errors::TaskTraderError, events::TaskReviewersUpdated, state::task_info::TaskInfo};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateTaskReviewers<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = task_info.requester == user.key() @ TaskTraderError::InvalidRequester,
+        constraint = task_info.is_active() @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+}
+
+/// Delegates reviewing to the given pubkeys. The requester can always review
+/// and an empty list removes every delegate.
+pub fn update_task_reviewers(
+    ctx: Context<UpdateTaskReviewers>,
+    reviewers: Vec<Pubkey>,
+) -> Result<()> {
+    msg!("Updating task reviewers...");
+
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.set_reviewers(reviewers.clone())?;
+
+    emit_cpi!(TaskReviewersUpdated {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        reviewers,
+    });
+
+    Ok(())
+}
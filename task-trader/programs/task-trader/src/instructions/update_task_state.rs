@@ -0,0 +1,40 @@
+use crate::{
+    errors::TaskTraderError,
+    events::TaskStateUpdated,
+    state::task_info::{TaskInfo, TaskState},
+};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateTaskState<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = task_info.requester == user.key() @ TaskTraderError::InvalidRequester,
+        constraint = task_info.is_active() @ TaskTraderError::InvalidTaskState,
+    )]
+    pub task_info: Account<'info, TaskInfo>,
+}
+
+/// Pauses or resumes a task. Closing and cancelling go through their own
+/// instructions because they move escrow.
+pub fn update_task_state(ctx: Context<UpdateTaskState>, state: TaskState) -> Result<()> {
+    msg!("Updating task state...");
+
+    if !matches!(state, TaskState::Open | TaskState::Paused) {
+        return Err(TaskTraderError::InvalidTaskState.into());
+    }
+
+    let task_info = &mut ctx.accounts.task_info;
+    task_info.state = state;
+
+    emit_cpi!(TaskStateUpdated {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        state,
+    });
+
+    Ok(())
+}
@@ -1,9 +1,11 @@
 use crate::{
     errors::TaskTraderError,
+    events::SupportCoinsUpdated,
     state::{admin::Admin, support_coin::SupportCoin},
 };
 use anchor_lang::prelude::*;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct UpdateTaskSupportCoin<'info> {
     #[account(mut)]
@@ -34,7 +36,18 @@ pub fn update_task_support_coin(
 ) -> Result<()> {
     msg!("Updating task support coin...");
 
-    ctx.accounts.support_coin.coin_mints = coin_mints;
+    if coin_mints.len() > SupportCoin::MAX_COINS {
+        return Err(TaskTraderError::InvalidLength.into());
+    }
+
+    let support_coin = &mut ctx.accounts.support_coin;
+    // Drop fee overrides of coins that are no longer supported
+    support_coin
+        .fee_overrides
+        .retain(|fee_override| coin_mints.contains(&fee_override.coin_mint));
+    support_coin.coin_mints = coin_mints.clone();
+
+    emit_cpi!(SupportCoinsUpdated { coin_mints });
 
     Ok(())
 }
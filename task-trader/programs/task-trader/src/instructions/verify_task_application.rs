@@ -1,20 +1,32 @@
 use crate::{
     errors::TaskTraderError,
+    events::ApplicationVerified,
     state::admin::Admin,
     state::task_application::{ApplicationState, TaskApplication},
-    state::task_info::TaskInfo,
+    state::task_info::{TaskInfo, TaskState},
+    state::user_stats::UserStats,
 };
 use anchor_lang::prelude::*;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct VerifyTaskApplication<'info> {
     #[account(
         mut,
-        constraint = task_application.state != ApplicationState::Withdrawed @ TaskTraderError::InvalidApplicationState,)]
+        constraint = !matches!(
+            task_application.state,
+            ApplicationState::Withdrawed | ApplicationState::Disputed
+        ) @ TaskTraderError::InvalidApplicationState,
+    )]
     pub task_application: Account<'info, TaskApplication>,
     #[account(
+        mut,
         constraint = task_info.task_id == task_application.task_id @ TaskTraderError::InvalidTaskId,
-        constraint = task_info.requester == user.key() @ TaskTraderError::InvalidRequester,
+        constraint = task_info.is_authorized_reviewer(&user.key(), &admin.signer) @ TaskTraderError::InvalidReviewer,
+        constraint = matches!(
+            task_info.state,
+            TaskState::Open | TaskState::Closed
+        ) @ TaskTraderError::InvalidTaskState,
     )]
     pub task_info: Account<'info, TaskInfo>,
 
@@ -25,21 +37,161 @@ pub struct VerifyTaskApplication<'info> {
     pub admin: Account<'info, Admin>,
 
     #[account(
-        mut,
-        constraint = user.key() == admin.signer
+        init_if_needed,
+        payer = user,
+        space = UserStats::INIT_SPACE,
+        seeds = [
+            b"user_stats",
+            task_application.applicant.as_ref(),
+            task_info.coin_mint.as_ref(),
+        ],
+        bump
     )]
+    pub applicant_stats: Account<'info, UserStats>,
+
+    #[account(mut)]
     pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// A reviewer's decision on one application.
+#[derive(Clone, Copy, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct ApplicationReview {
+    pub is_accepted: bool,
+    pub rejection_reason: Option<u16>,
+    pub milestone_index: Option<u8>,
+    pub awarded_amount: Option<u64>,
 }
 
+/// Reviews one application. Once the task is closed or expired, the only
+/// review left is approving the next milestone of an accepted application, so
+/// the escrow kept for its remaining milestones can still be paid out.
 pub fn verify_task_application(
     ctx: Context<VerifyTaskApplication>,
     is_accepted: bool,
+    rejection_reason: Option<u16>,
+    milestone_index: Option<u8>,
+    awarded_amount: Option<u64>,
 ) -> Result<()> {
+    let task_info = &mut ctx.accounts.task_info;
+    let now = Clock::get()?.unix_timestamp;
     let task_application = &mut ctx.accounts.task_application;
-    task_application.state = if is_accepted {
-        ApplicationState::AcceptedByAcceptance
+    let previous_state = task_application.state;
+    review_application(
+        task_info,
+        task_application,
+        &ApplicationReview {
+            is_accepted,
+            rejection_reason,
+            milestone_index,
+            awarded_amount,
+        },
+        now,
+    )?;
+
+    let applicant_stats = &mut ctx.accounts.applicant_stats;
+    applicant_stats.initialize(
+        task_application.applicant,
+        task_info.coin_mint,
+        ctx.bumps.applicant_stats,
+    );
+    applicant_stats.record_review(previous_state, task_application.state)?;
+
+    emit_cpi!(ApplicationVerified {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        applicant: task_application.applicant,
+        reviewer: ctx.accounts.user.key(),
+        is_accepted,
+        rejection_reason: task_application.rejection_reason,
+        approved_milestones: task_application.approved_milestones,
+        awarded_amount: task_application.awarded_amount,
+    });
+    Ok(())
+}
+
+/// Applies `review` to an application of `task_info` at time `now`. The task
+/// is only updated once every check passed, so a failed review leaves it untouched.
+pub(crate) fn review_application(
+    task_info: &mut TaskInfo,
+    task_application: &mut TaskApplication,
+    review: &ApplicationReview,
+    now: i64,
+) -> Result<()> {
+    let approves_next_milestone = review.is_accepted
+        && review.milestone_index.is_some()
+        && task_application.state == ApplicationState::AcceptedByAcceptance;
+    if !approves_next_milestone {
+        if task_info.state != TaskState::Open {
+            return Err(TaskTraderError::InvalidTaskState.into());
+        }
+        if task_info.is_expired(now) {
+            return Err(TaskTraderError::TaskExpired.into());
+        }
+    }
+    if matches!(
+        task_application.state,
+        ApplicationState::Withdrawed | ApplicationState::Disputed
+    ) {
+        return Err(TaskTraderError::InvalidApplicationState.into());
+    }
+    let was_accepted = task_application.state == ApplicationState::AcceptedByAcceptance;
+    let was_disputable = task_application.is_disputable();
+
+    // Paid milestones cannot be taken back, so the acceptance sticks once anything was paid
+    if !review.is_accepted && task_application.paid_milestones > 0 {
+        return Err(TaskTraderError::InvalidApplicationState.into());
+    }
+    // A vesting payout is priced once claims started, so it can no longer be reviewed
+    if task_info.vesting.is_some() && task_application.claimed_amount > 0 {
+        return Err(TaskTraderError::InvalidApplicationState.into());
+    }
+
+    if review.is_accepted {
+        // Only delivered work can be accepted; accepted applications keep being reviewed
+        if !was_accepted && task_application.state != ApplicationState::Submitted {
+            return Err(TaskTraderError::InvalidApplicationState.into());
+        }
+        // Awards replace the single payout, so they cannot be combined with milestones
+        if let Some(amount) = review.awarded_amount {
+            if !task_info.milestones.is_empty() {
+                return Err(TaskTraderError::InvalidMilestones.into());
+            }
+            if amount > task_info.task_amount {
+                return Err(TaskTraderError::InvalidAmount.into());
+            }
+        }
+        match review.milestone_index {
+            Some(index) => {
+                task_application.approve_milestone(index, task_info.milestone_count())?
+            }
+            // Accepting a task without milestones approves its single payout
+            None if task_info.milestones.is_empty() => task_application.approved_milestones = 1,
+            None => {}
+        }
+        task_application.state = ApplicationState::AcceptedByAcceptance;
+        task_application.rejection_reason = None;
+        task_application.awarded_amount = review.awarded_amount;
+        // Vesting runs from the first acceptance
+        if !was_accepted {
+            task_application.accepted_at = now;
+        }
     } else {
-        ApplicationState::Applied
-    };
+        task_application.state = ApplicationState::Rejected;
+        task_application.rejection_reason = review.rejection_reason;
+        task_application.dispute_deadline = now.saturating_add(TaskApplication::DISPUTE_WINDOW);
+        task_application.approved_milestones = 0;
+        task_application.awarded_amount = None;
+    }
+
+    // Keep the accepted seat count in sync so refunds never touch reserved escrow
+    if review.is_accepted && !was_accepted {
+        task_info.record_acceptance()?;
+    } else if !review.is_accepted && was_accepted {
+        task_info.revert_acceptance()?;
+    }
+    task_info.record_disputable(was_disputable, task_application.is_disputable())?;
+
     Ok(())
 }
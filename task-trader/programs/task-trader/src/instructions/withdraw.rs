@@ -1,18 +1,24 @@
 use crate::{
     errors::TaskTraderError,
+    events::RewardWithdrawn,
     state::{
         admin::Admin,
+        referral::Referral,
+        support_coin::SupportCoin,
         task_application::{ApplicationState, TaskApplication},
-        task_info::TaskInfo,
+        task_info::{TaskInfo, TaskState},
+        treasury::Treasury,
+        user_stats::UserStats,
     },
-    utils::token_utils,
+    utils::{fee_utils, token_utils},
 };
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     #[account(mut)]
@@ -24,6 +30,12 @@ pub struct Withdraw<'info> {
     )]
     pub admin: Account<'info, Admin>,
 
+    #[account(
+        seeds = [b"support_coin"],
+        bump,
+    )]
+    pub support_coin: Account<'info, SupportCoin>,
+
     #[account(
         mut,
         constraint = task_application.state == ApplicationState::AcceptedByAcceptance @ TaskTraderError::InvalidApplicationState,
@@ -34,30 +46,48 @@ pub struct Withdraw<'info> {
     #[account(
         mut,
         constraint = task_info.task_id == task_application.task_id @ TaskTraderError::InvalidTaskId,
+        constraint = task_info.state != TaskState::Cancelled @ TaskTraderError::InvalidTaskState,
     )]
     pub task_info: Account<'info, TaskInfo>,
 
     /// CHECK: This is not dangerous
     #[account(
-        seeds = [b"pool_authority"],
+        seeds = [b"vault_authority", task_info.key().as_ref()],
         bump,
-        constraint = pool_authority.key() == Pubkey::find_program_address(&[b"pool_authority"], &crate::ID).0
     )]
-    pub pool_authority: AccountInfo<'info>,
+    pub vault_authority: AccountInfo<'info>,
 
     #[account(
         mut,
         constraint = coin_mint.key() == task_info.coin_mint @ TaskTraderError::InvalidMint,
+        mint::token_program = token_program,
     )]
-    pub coin_mint: Account<'info, Mint>,
+    pub coin_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = coin_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_coin_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: This is not dangerous
+    #[account(
+        constraint = requester.key() == task_info.requester @ TaskTraderError::InvalidRequester
+    )]
+    pub requester: Option<AccountInfo<'info>>,
+
+    /// Receives the part of the task amount that was not awarded
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = coin_mint,
+        associated_token::authority = requester,
+        associated_token::token_program = token_program,
     )]
-    pub user_coin_account: Box<Account<'info, TokenAccount>>,
+    pub requester_coin_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     /// CHECK: This is not dangerous
     #[account(mut)]
@@ -68,122 +98,386 @@ pub struct Withdraw<'info> {
         payer = user,
         associated_token::mint = coin_mint,
         associated_token::authority = inviter,
+        associated_token::token_program = token_program,
     )]
-    pub inviter_coin_account: Option<Box<Account<'info, TokenAccount>>>,
+    pub inviter_coin_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: Referral of the inviter, naming the second referral level; checked in the handler
+    pub inviter_referral: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub second_level_coin_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: Referral of the second level, naming the third referral level; checked in the handler
+    pub second_level_referral: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub third_level_coin_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
         mut,
-        constraint = pool_coin_account.owner == pool_authority.key() @ TaskTraderError::InvalidPoolAccount,
-        constraint = pool_coin_account.mint == coin_mint.key() @ TaskTraderError::InvalidMint,
+        seeds = [b"task_vault", task_info.key().as_ref()],
+        bump,
+        constraint = task_vault.amount >= task_info.escrow_amount @ TaskTraderError::InvalidPoolAccount,
     )]
-    pub pool_coin_account: Box<Account<'info, TokenAccount>>,
+    pub task_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// CHECK: This is not dangerous
     #[account(
-        constraint = fee_receiver.key() == admin.fee_receiver @ TaskTraderError::InvalidFeeReceiverAccount
+        mut,
+        seeds = [b"treasury", coin_mint.key().as_ref()],
+        bump = treasury.bump,
     )]
-    pub fee_receiver: AccountInfo<'info>,
+    pub treasury: Box<Account<'info, Treasury>>,
 
     #[account(
         mut,
-        constraint = fee_receiver_coin_account.owner == fee_receiver.key() @ TaskTraderError::InvalidFeeReceiverAccount,
-        constraint = fee_receiver_coin_account.mint == coin_mint.key() @ TaskTraderError::InvalidMint,
+        seeds = [b"treasury_vault", coin_mint.key().as_ref()],
+        bump,
     )]
-    pub fee_receiver_coin_account: Box<Account<'info, TokenAccount>>,
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserStats::INIT_SPACE,
+        seeds = [b"user_stats", user.key().as_ref(), coin_mint.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Box<Account<'info, UserStats>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
-pub fn withdraw(ctx: Context<Withdraw>) -> Result<()> {
+pub fn withdraw<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>) -> Result<()> {
     let task_info = &ctx.accounts.task_info;
     let task_application = &ctx.accounts.task_application;
-    let seeds = &[b"pool_authority".as_ref(), &[ctx.bumps.pool_authority]];
+    let task_info_key = task_info.key();
+    let seeds = &[
+        b"vault_authority".as_ref(),
+        task_info_key.as_ref(),
+        &[ctx.bumps.vault_authority],
+    ];
 
-    // Calculate fee for task amount (0.1%) with safe math
-    let task_fee = task_info
-        .task_amount
-        .checked_mul(ctx.accounts.admin.fee_ratio)
-        .and_then(|product| product.checked_div(1000))
-        .ok_or(TaskTraderError::NumericalOverflow)?;
-    let task_amount_after_fee = task_info
-        .task_amount
-        .checked_sub(task_fee)
-        .ok_or(TaskTraderError::NumericalOverflow)?;
+    let (task_fee_bps, reward_fee_bps) = ctx
+        .accounts
+        .admin
+        .fee_rates(&ctx.accounts.support_coin, &task_info.coin_mint);
+
+    let payout = milestone_payout(
+        task_info,
+        task_application,
+        task_fee_bps,
+        Clock::get()?.unix_timestamp,
+    )?;
+    let MilestonePayout {
+        task_fee,
+        task_amount_after_fee,
+        requester_refund,
+        is_final_payout,
+        ..
+    } = payout;
+
+    // Rewards are paid with the final milestone and split across the referral chain.
+    // Shares of levels that do not exist go to the treasury along with the rewards fee.
+    let mut rewards_fee: u64 = 0;
+    let mut level_payouts = Vec::new();
+    let mut inviter = None;
 
-    // Calculate total fee including potential rewards fee
-    let mut total_fee = task_fee;
-    let mut rewards_after_fee = 0;
-
-    if task_application.inviter != Pubkey::default() && task_info.rewards > 0 {
-        if let Some(inviter_account) = &ctx.accounts.inviter_coin_account {
-            if let Some(inviter) = &ctx.accounts.inviter {
-                if inviter.key() != inviter_account.owner
-                    || inviter.key() != task_application.inviter
-                {
-                    return Err(TaskTraderError::InvalidInviter.into());
-                }
-                // Calculate fee for rewards (0.1%) with safe math
-                let rewards_fee = task_info
-                    .rewards
-                    .checked_div(1000)
-                    .and_then(|rewards_fee| rewards_fee.checked_mul(1))
-                    .ok_or(TaskTraderError::NumericalOverflow)?;
-                total_fee = total_fee
-                    .checked_add(rewards_fee)
-                    .ok_or(TaskTraderError::NumericalOverflow)?;
-                rewards_after_fee = task_info
-                    .rewards
-                    .checked_sub(rewards_fee)
-                    .ok_or(TaskTraderError::NumericalOverflow)?;
-            }
+    if is_final_payout && task_info.rewards > 0 {
+        let levels = referral_levels(ctx.accounts)?;
+        inviter = levels.first().map(|(inviter, _)| *inviter);
+        let shares = fee_utils::split_referral_rewards(
+            task_info.rewards,
+            &ctx.accounts.admin.referral_splits_bps[..levels.len()],
+        )?;
+        let mut distributed: u64 = 0;
+        for ((_, coin_account), share) in levels.into_iter().zip(shares) {
+            let (share_fee, share_after_fee) = fee_utils::split_fee(share, reward_fee_bps)?;
+            rewards_fee = rewards_fee
+                .checked_add(share_fee)
+                .ok_or(TaskTraderError::NumericalOverflow)?;
+            distributed = distributed
+                .checked_add(share)
+                .ok_or(TaskTraderError::NumericalOverflow)?;
+            level_payouts.push((coin_account, share_after_fee));
         }
-    } else if task_info.rewards > 0 {
-        total_fee = total_fee
-            .checked_add(task_info.rewards)
+        rewards_fee = task_info
+            .rewards
+            .checked_sub(distributed)
+            .and_then(|undistributed| undistributed.checked_add(rewards_fee))
             .ok_or(TaskTraderError::NumericalOverflow)?;
     }
+    let level_rewards: Vec<u64> = level_payouts.iter().map(|(_, amount)| *amount).collect();
+    let rewards_after_fee: u64 = level_rewards.iter().sum();
+    let total_fee = task_fee
+        .checked_add(rewards_fee)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
 
-    // Transfer total fee to fee receiver
+    // Transfer total fee to the treasury
     if total_fee > 0 {
         token_utils::transfer_token_with_singer(
             ctx.accounts.token_program.to_account_info(),
-            ctx.accounts.pool_coin_account.to_account_info(),
-            ctx.accounts.fee_receiver_coin_account.to_account_info(),
-            ctx.accounts.pool_authority.to_account_info(),
+            ctx.accounts.task_vault.to_account_info(),
+            ctx.accounts.coin_mint.to_account_info(),
+            ctx.accounts.treasury_vault.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            ctx.remaining_accounts,
             total_fee,
+            ctx.accounts.coin_mint.decimals,
             Some(&[seeds]),
         )?;
+        ctx.accounts
+            .treasury
+            .accrue(token_utils::net_of_transfer_fee(
+                &ctx.accounts.coin_mint.to_account_info(),
+                total_fee,
+            )?)?;
     }
 
     // Transfer remaining amount to user
     token_utils::transfer_token_with_singer(
         ctx.accounts.token_program.to_account_info(),
-        ctx.accounts.pool_coin_account.to_account_info(),
+        ctx.accounts.task_vault.to_account_info(),
+        ctx.accounts.coin_mint.to_account_info(),
         ctx.accounts.user_coin_account.to_account_info(),
-        ctx.accounts.pool_authority.to_account_info(),
+        ctx.accounts.vault_authority.to_account_info(),
+        ctx.remaining_accounts,
         task_amount_after_fee,
+        ctx.accounts.coin_mint.decimals,
         Some(&[seeds]),
     )?;
 
-    // Transfer remaining rewards to inviter if applicable
-    if rewards_after_fee > 0 {
-        if let Some(inviter_account) = &ctx.accounts.inviter_coin_account {
-            token_utils::transfer_token_with_singer(
-                ctx.accounts.token_program.to_account_info(),
-                ctx.accounts.pool_coin_account.to_account_info(),
-                inviter_account.to_account_info(),
-                ctx.accounts.pool_authority.to_account_info(),
-                rewards_after_fee,
-                Some(&[seeds]),
-            )?;
+    // Return the unawarded part of the task amount to the requester
+    if requester_refund > 0 {
+        let Some(requester_coin_account) = &ctx.accounts.requester_coin_account else {
+            return Err(TaskTraderError::InvalidRequester.into());
+        };
+        token_utils::transfer_token_with_singer(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.task_vault.to_account_info(),
+            ctx.accounts.coin_mint.to_account_info(),
+            requester_coin_account.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            ctx.remaining_accounts,
+            requester_refund,
+            ctx.accounts.coin_mint.decimals,
+            Some(&[seeds]),
+        )?;
+    }
+
+    // Transfer each referral level its share of the rewards
+    for (coin_account, amount) in level_payouts {
+        if amount == 0 {
+            continue;
         }
+        token_utils::transfer_token_with_singer(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.task_vault.to_account_info(),
+            ctx.accounts.coin_mint.to_account_info(),
+            coin_account,
+            ctx.accounts.vault_authority.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            ctx.accounts.coin_mint.decimals,
+            Some(&[seeds]),
+        )?;
     }
 
+    // Everything paid out of the vault for this application: fees, payout, refund and rewards
+    let released_amount = total_fee
+        .checked_add(task_amount_after_fee)
+        .and_then(|amount| amount.checked_add(requester_refund))
+        .and_then(|amount| amount.checked_add(rewards_after_fee))
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+
+    let task_info = &mut ctx.accounts.task_info;
     let task_application = &mut ctx.accounts.task_application;
-    task_application.state = ApplicationState::Withdrawed;
+    record_withdrawal(task_info, task_application, released_amount, &payout)?;
+
+    let user_stats = &mut ctx.accounts.user_stats;
+    user_stats.initialize(
+        task_application.applicant,
+        task_info.coin_mint,
+        ctx.bumps.user_stats,
+    );
+    user_stats.record_payout(task_amount_after_fee, is_final_payout)?;
+
+    emit_cpi!(RewardWithdrawn {
+        task_info: task_info.key(),
+        task_id: task_info.task_id,
+        applicant: task_application.applicant,
+        coin_mint: task_info.coin_mint,
+        task_amount: task_amount_after_fee,
+        task_fee,
+        inviter,
+        inviter_rewards: rewards_after_fee,
+        level_rewards,
+        rewards_fee,
+        paid_milestones: task_application.paid_milestones,
+        requester_refund,
+        claimed_amount: task_application.claimed_amount,
+    });
+
+    // Hand native SOL payouts back as lamports rather than wSOL
+    if token_utils::is_native_mint(&ctx.accounts.coin_mint.key()) {
+        token_utils::unwrap_sol(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_coin_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+        )?;
+    }
 
     Ok(())
 }
+
+/// What an accepted application is owed for its approved but unpaid milestones.
+#[derive(Clone, Copy)]
+pub(crate) struct MilestonePayout {
+    pub task_fee: u64,
+    pub task_amount_after_fee: u64,
+    /// Unawarded part of the task amount, owed back to the requester
+    pub requester_refund: u64,
+    pub approved_milestones: u8,
+    /// Whether this payout completes the application, which also pays the rewards
+    pub is_final_payout: bool,
+}
+
+/// Prices every approved milestone that has not been paid yet, each with its own fee.
+/// On vesting tasks only the part of the payout vested at `now` and not yet
+/// claimed is owed; the fee and the requester refund are settled with the first claim.
+pub(crate) fn milestone_payout(
+    task_info: &TaskInfo,
+    task_application: &TaskApplication,
+    task_fee_bps: u16,
+    now: i64,
+) -> Result<MilestonePayout> {
+    let paid_milestones = task_application.paid_milestones;
+    let approved_milestones = task_application.approved_milestones;
+    if approved_milestones <= paid_milestones {
+        return Err(TaskTraderError::NoApprovedMilestone.into());
+    }
+    let mut task_fee: u64 = 0;
+    let mut task_amount_after_fee: u64 = 0;
+    let mut requester_refund: u64 = 0;
+    for index in paid_milestones..approved_milestones {
+        let mut milestone_amount = task_info.milestone_amount(index)?;
+        // An award only exists on tasks without milestones and replaces their single payout
+        if let Some(awarded_amount) = task_application.awarded_amount {
+            requester_refund = milestone_amount
+                .checked_sub(awarded_amount)
+                .ok_or(TaskTraderError::NumericalOverflow)?;
+            milestone_amount = awarded_amount;
+        }
+        let (milestone_fee, milestone_after_fee) =
+            fee_utils::split_fee(milestone_amount, task_fee_bps)?;
+        task_fee = task_fee
+            .checked_add(milestone_fee)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        task_amount_after_fee = task_amount_after_fee
+            .checked_add(milestone_after_fee)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+    }
+
+    // A zero award has nothing to vest and settles as a single final payout
+    if let Some(vesting) = task_info.vesting.filter(|_| task_amount_after_fee > 0) {
+        let vested_amount =
+            vesting.vested_amount(task_amount_after_fee, task_application.accepted_at, now)?;
+        let claimable_amount = vested_amount
+            .checked_sub(task_application.claimed_amount)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        if claimable_amount == 0 {
+            return Err(TaskTraderError::NothingVested.into());
+        }
+        let is_first_claim = task_application.claimed_amount == 0;
+        return Ok(MilestonePayout {
+            task_fee: if is_first_claim { task_fee } else { 0 },
+            task_amount_after_fee: claimable_amount,
+            requester_refund: if is_first_claim { requester_refund } else { 0 },
+            approved_milestones,
+            is_final_payout: vested_amount == task_amount_after_fee,
+        });
+    }
+
+    Ok(MilestonePayout {
+        task_fee,
+        task_amount_after_fee,
+        requester_refund,
+        approved_milestones,
+        is_final_payout: approved_milestones == task_info.milestone_count(),
+    })
+}
+
+/// Books `payout`, which released `released_amount` from the vault in total.
+pub(crate) fn record_withdrawal(
+    task_info: &mut TaskInfo,
+    task_application: &mut TaskApplication,
+    released_amount: u64,
+    payout: &MilestonePayout,
+) -> Result<()> {
+    task_info.release_escrow(released_amount)?;
+
+    task_application.claimed_amount = task_application
+        .claimed_amount
+        .checked_add(payout.task_amount_after_fee)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+    // A vesting payout stays unpaid until it is fully claimed
+    if task_info.vesting.is_none() || payout.is_final_payout {
+        task_application.paid_milestones = payout.approved_milestones;
+    }
+    if payout.is_final_payout {
+        task_info.record_payout()?;
+        task_application.state = ApplicationState::Withdrawed;
+    }
+
+    Ok(())
+}
+
+/// Inviters of the referral chain with their token accounts, starting with
+/// the inviter saved on the application. Deeper levels are read from the
+/// `Referral` accounts, each of which is required while the previous level
+/// has an inviter, so a level is only left out when it does not exist. A
+/// cycle ends the chain.
+fn referral_levels<'info>(accounts: &Withdraw<'info>) -> Result<Vec<(Pubkey, AccountInfo<'info>)>> {
+    let coin_mint = accounts.coin_mint.key();
+    let coin_accounts = [
+        &accounts.inviter_coin_account,
+        &accounts.second_level_coin_account,
+        &accounts.third_level_coin_account,
+    ];
+    let next_referrals = [&accounts.inviter_referral, &accounts.second_level_referral];
+
+    let mut levels = Vec::new();
+    let mut seen = vec![accounts.user.key()];
+    // The first level is the inviter the application was gated on when applying
+    let mut next_inviter =
+        Some(accounts.task_application.inviter).filter(|inviter| *inviter != Pubkey::default());
+    for (level, coin_account) in coin_accounts.into_iter().enumerate() {
+        let Some(inviter) = next_inviter else {
+            break;
+        };
+        if seen.contains(&inviter) {
+            break;
+        }
+        let Some(coin_account) = coin_account else {
+            return Err(TaskTraderError::InvalidInviter.into());
+        };
+        if coin_account.owner != inviter || coin_account.mint != coin_mint {
+            return Err(TaskTraderError::InvalidInviter.into());
+        }
+        levels.push((inviter, coin_account.to_account_info()));
+        seen.push(inviter);
+
+        let referral = match next_referrals.get(level) {
+            Some(Some(next_referral)) => Referral::load(next_referral, &inviter)?,
+            Some(None) => return Err(TaskTraderError::InvalidInviter.into()),
+            None => None,
+        };
+        next_inviter = referral.map(|referral| referral.inviter);
+    }
+
+    Ok(levels)
+}
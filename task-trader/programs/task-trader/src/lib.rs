@@ -1,10 +1,12 @@
 mod errors;
+mod events;
 mod instructions;
 mod state;
 mod utils;
 
 use anchor_lang::prelude::*;
 use instructions::*;
+use state::task_info::{SbtGate, TaskMetadata, TaskState, Vesting};
 
 declare_id!("DSyKrLRc83jxeEUiUJdsyePRcreQ2dkXj3vdpggH8wd1");
 
@@ -15,59 +17,134 @@ pub mod task_trader {
     pub fn initialize(
         ctx: Context<Initialize>,
         signer: Pubkey,
-        fee_receiver: Pubkey,
-        fee_ratio: u64,
+        task_fee_bps: u16,
+        reward_fee_bps: u16,
+        arbiter: Pubkey,
     ) -> Result<()> {
         msg!("Initializing...");
 
-        instructions::initialize::initialize(ctx, signer, fee_receiver, fee_ratio)
+        instructions::initialize::initialize(ctx, signer, task_fee_bps, reward_fee_bps, arbiter)
     }
 
     pub fn update_admin(
         ctx: Context<UpdateAdmin>,
         signer: Pubkey,
-        fee_receiver: Pubkey,
-        fee_ratio: u64,
+        task_fee_bps: u16,
+        reward_fee_bps: u16,
+        arbiter: Pubkey,
     ) -> Result<()> {
         msg!("Updating admin...");
 
-        instructions::update_admin::update_admin(ctx, signer, fee_receiver, fee_ratio)
+        instructions::update_admin::update_admin(ctx, signer, task_fee_bps, reward_fee_bps, arbiter)
     }
 
-    pub fn create_task(
-        ctx: Context<CreateTask>,
-        task_id: u64,
+    pub fn initialize_task_counter(
+        ctx: Context<InitializeTaskCounter>,
+        next_task_id: u64,
+    ) -> Result<()> {
+        msg!("Initializing task counter...");
+        instructions::initialize_task_counter::initialize_task_counter(ctx, next_task_id)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_task<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateTask<'info>>,
         task_amount: u64,
         taker_num: u64,
-        coin_mint: Pubkey, // usdt, mai
-        rewards: u64,      // mai
-    ) -> Result<()> {
+        rewards: u64, // mai
+        apply_deadline: i64,
+        complete_deadline: i64,
+        allowed_applicants: Vec<Pubkey>,
+        allowlist_root: Option<[u8; 32]>,
+        milestones: Vec<u64>,
+        sbt_gate: Option<SbtGate>,
+        vesting: Option<Vesting>,
+        metadata: TaskMetadata,
+    ) -> Result<u64> {
         msg!("Creating Task Trader...");
 
         instructions::create_task::create_task(
             ctx,
-            task_id,
             task_amount,
             taker_num,
-            coin_mint,
             rewards,
+            apply_deadline,
+            complete_deadline,
+            allowed_applicants,
+            allowlist_root,
+            milestones,
+            sbt_gate,
+            vesting,
+            metadata,
         )
     }
 
-    pub fn apply_task(ctx: Context<ApplyTask>, inviter: Option<Pubkey>) -> Result<()> {
+    pub fn apply_task(
+        ctx: Context<ApplyTask>,
+        allowlist_proof: Option<Vec<[u8; 32]>>,
+    ) -> Result<()> {
         msg!("Applying Task Trader...");
-        instructions::apply_task::apply_task(ctx, inviter)
+        instructions::apply_task::apply_task(ctx, allowlist_proof)
+    }
+
+    pub fn submit_work(
+        ctx: Context<SubmitWork>,
+        work_hash: [u8; 32],
+        work_uri: String,
+    ) -> Result<()> {
+        msg!("Submitting work...");
+        instructions::submit_work::submit_work(ctx, work_hash, work_uri)
     }
 
     pub fn verify_task_application(
         ctx: Context<VerifyTaskApplication>,
         is_accepted: bool,
+        rejection_reason: Option<u16>,
+        milestone_index: Option<u8>,
+        awarded_amount: Option<u64>,
     ) -> Result<()> {
         msg!("Verifying task application...");
-        instructions::verify_task_application::verify_task_application(ctx, is_accepted)
+        instructions::verify_task_application::verify_task_application(
+            ctx,
+            is_accepted,
+            rejection_reason,
+            milestone_index,
+            awarded_amount,
+        )
+    }
+
+    pub fn accept_with_attestation(
+        ctx: Context<AcceptWithAttestation>,
+        attestation: AcceptanceAttestation,
+    ) -> Result<()> {
+        msg!("Accepting task application with attestation...");
+        instructions::accept_with_attestation::accept_with_attestation(ctx, attestation)
+    }
+
+    pub fn batch_verify_applications<'info>(
+        ctx: Context<'_, '_, '_, 'info, BatchVerifyApplications<'info>>,
+        reviews: Vec<ApplicationReview>,
+    ) -> Result<()> {
+        msg!("Verifying task applications...");
+        instructions::batch_verify_applications::batch_verify_applications(ctx, reviews)
+    }
+
+    pub fn batch_payout<'info>(ctx: Context<'_, '_, '_, 'info, BatchPayout<'info>>) -> Result<()> {
+        msg!("Paying out task applications...");
+        instructions::batch_payout::batch_payout(ctx)
+    }
+
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+        msg!("Initializing treasury...");
+        instructions::initialize_treasury::initialize_treasury(ctx)
     }
 
-    pub fn withdraw(ctx: Context<Withdraw>) -> Result<()> {
+    pub fn collect_fees<'info>(ctx: Context<'_, '_, '_, 'info, CollectFees<'info>>) -> Result<()> {
+        msg!("Collecting fees...");
+        instructions::collect_fees::collect_fees(ctx)
+    }
+
+    pub fn withdraw<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>) -> Result<()> {
         msg!("Withdrawing rewards...");
         instructions::withdraw::withdraw(ctx)
     }
@@ -80,4 +157,159 @@ pub mod task_trader {
         instructions::update_task_support_coin::update_task_support_coin(ctx, coin_mints)
     }
 
+    pub fn update_coin_fee_override(
+        ctx: Context<UpdateCoinFeeOverride>,
+        coin_mint: Pubkey,
+        task_fee_bps: Option<u16>,
+        reward_fee_bps: Option<u16>,
+    ) -> Result<()> {
+        msg!("Updating coin fee override...");
+        instructions::update_coin_fee_override::update_coin_fee_override(
+            ctx,
+            coin_mint,
+            task_fee_bps,
+            reward_fee_bps,
+        )
+    }
+
+    pub fn update_task_state(ctx: Context<UpdateTaskState>, state: TaskState) -> Result<()> {
+        msg!("Updating task state...");
+        instructions::update_task_state::update_task_state(ctx, state)
+    }
+
+    pub fn close_task<'info>(ctx: Context<'_, '_, '_, 'info, CloseTask<'info>>) -> Result<()> {
+        msg!("Closing task...");
+        instructions::close_task::close_task(ctx)
+    }
+
+    pub fn cancel_task<'info>(ctx: Context<'_, '_, '_, 'info, CloseTask<'info>>) -> Result<()> {
+        msg!("Cancelling task...");
+        instructions::close_task::cancel_task(ctx)
+    }
+
+    pub fn reclaim_expired_task<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReclaimExpiredTask<'info>>,
+    ) -> Result<()> {
+        msg!("Reclaiming expired task...");
+        instructions::reclaim_expired_task::reclaim_expired_task(ctx)
+    }
+
+    pub fn increase_taker_num<'info>(
+        ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+        additional_takers: u64,
+    ) -> Result<()> {
+        msg!("Increasing taker number...");
+        instructions::adjust_task::increase_taker_num(ctx, additional_takers)
+    }
+
+    pub fn increase_task_amount<'info>(
+        ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+        additional_amount: u64,
+    ) -> Result<()> {
+        msg!("Increasing task amount...");
+        instructions::adjust_task::increase_task_amount(ctx, additional_amount)
+    }
+
+    pub fn increase_rewards<'info>(
+        ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+        additional_rewards: u64,
+    ) -> Result<()> {
+        msg!("Increasing rewards...");
+        instructions::adjust_task::increase_rewards(ctx, additional_rewards)
+    }
+
+    pub fn decrease_taker_num<'info>(
+        ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+        removed_takers: u64,
+    ) -> Result<()> {
+        msg!("Decreasing taker number...");
+        instructions::adjust_task::decrease_taker_num(ctx, removed_takers)
+    }
+
+    pub fn decrease_task_amount<'info>(
+        ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+        removed_amount: u64,
+    ) -> Result<()> {
+        msg!("Decreasing task amount...");
+        instructions::adjust_task::decrease_task_amount(ctx, removed_amount)
+    }
+
+    pub fn decrease_rewards<'info>(
+        ctx: Context<'_, '_, '_, 'info, AdjustTask<'info>>,
+        removed_rewards: u64,
+    ) -> Result<()> {
+        msg!("Decreasing rewards...");
+        instructions::adjust_task::decrease_rewards(ctx, removed_rewards)
+    }
+
+    pub fn update_task_allowlist(
+        ctx: Context<UpdateTaskAllowlist>,
+        allowed_applicants: Vec<Pubkey>,
+        allowlist_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        msg!("Updating task allowlist...");
+        instructions::update_task_allowlist::update_task_allowlist(
+            ctx,
+            allowed_applicants,
+            allowlist_root,
+        )
+    }
+
+    pub fn update_task_reviewers(
+        ctx: Context<UpdateTaskReviewers>,
+        reviewers: Vec<Pubkey>,
+    ) -> Result<()> {
+        msg!("Updating task reviewers...");
+        instructions::update_task_reviewers::update_task_reviewers(ctx, reviewers)
+    }
+
+    pub fn update_task_metadata(
+        ctx: Context<UpdateTaskMetadata>,
+        metadata: TaskMetadata,
+    ) -> Result<()> {
+        msg!("Updating task metadata...");
+        instructions::update_task_metadata::update_task_metadata(ctx, metadata)
+    }
+
+    pub fn cancel_application(ctx: Context<CancelApplication>) -> Result<()> {
+        msg!("Cancelling task application...");
+        instructions::cancel_application::cancel_application(ctx)
+    }
+
+    pub fn close_application(ctx: Context<CloseApplication>) -> Result<()> {
+        msg!("Closing task application...");
+        instructions::close_application::close_application(ctx)
+    }
+
+    pub fn update_referral_splits(
+        ctx: Context<UpdateReferralSplits>,
+        referral_splits_bps: [u16; 3],
+    ) -> Result<()> {
+        msg!("Updating referral splits...");
+        instructions::update_referral_splits::update_referral_splits(ctx, referral_splits_bps)
+    }
+
+    pub fn register_referral(ctx: Context<RegisterReferral>, inviter: Pubkey) -> Result<()> {
+        msg!("Registering referral...");
+        instructions::register_referral::register_referral(ctx, inviter)
+    }
+
+    pub fn raise_dispute(ctx: Context<RaiseDispute>) -> Result<()> {
+        msg!("Raising dispute...");
+        instructions::raise_dispute::raise_dispute(ctx)
+    }
+
+    pub fn resolve_dispute<'info>(
+        ctx: Context<'_, '_, '_, 'info, ResolveDispute<'info>>,
+        applicant_amount: u64,
+    ) -> Result<()> {
+        msg!("Resolving dispute...");
+        instructions::resolve_dispute::resolve_dispute(ctx, applicant_amount)
+    }
+
+    pub fn lapse_dispute(ctx: Context<LapseDispute>) -> Result<()> {
+        msg!("Lapsing dispute...");
+        instructions::lapse_dispute::lapse_dispute(ctx)
+    }
+
 }
@@ -1,12 +1,32 @@
 use anchor_lang::prelude::*;
 
+use crate::state::support_coin::SupportCoin;
+
 #[account]
 pub struct Admin {
     pub signer: Pubkey,
-    pub fee_receiver: Pubkey,
-    pub fee_ratio: u64,
+    pub task_fee_bps: u16,
+    pub reward_fee_bps: u16,
+    pub arbiter: Pubkey,
+    pub referral_splits_bps: [u16; Admin::MAX_REFERRAL_LEVELS],
 }
 
 impl Admin {
-    pub const INIT_SPACE: usize = 8 + 32 + 32 + 8;
+    pub const MAX_REFERRAL_LEVELS: usize = 3;
+    /// Without explicit splits the direct inviter receives all of the rewards.
+    pub const DEFAULT_REFERRAL_SPLITS_BPS: [u16; Self::MAX_REFERRAL_LEVELS] = [10_000, 0, 0];
+    pub const INIT_SPACE: usize = 8 + 32 + 2 + 2 + 32 + (2 * Self::MAX_REFERRAL_LEVELS);
+
+    /// Task and reward fee rates for `coin_mint`, with per-mint overrides taking precedence.
+    pub fn fee_rates(&self, support_coin: &SupportCoin, coin_mint: &Pubkey) -> (u16, u16) {
+        let fee_override = support_coin.fee_override(coin_mint);
+        (
+            fee_override
+                .and_then(|fee_override| fee_override.task_fee_bps)
+                .unwrap_or(self.task_fee_bps),
+            fee_override
+                .and_then(|fee_override| fee_override.reward_fee_bps)
+                .unwrap_or(self.reward_fee_bps),
+        )
+    }
 }
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+/// Marks an acceptance attestation as used. Stored at
+/// `[b"attestation_nonce", reviewer, nonce]`, so a signed attestation can only
+/// be submitted once.
+#[account]
+pub struct AttestationNonce {
+    pub reviewer: Pubkey,
+    pub nonce: u64,
+}
+
+impl AttestationNonce {
+    pub const INIT_SPACE: usize = 8 + 32 + 8;
+}
@@ -1,4 +1,9 @@
 pub mod admin;
 pub mod task_info;
 pub mod task_application;
-pub mod support_coin;
\ No newline at end of file
+pub mod support_coin;
+pub mod referral;
+pub mod task_counter;
+pub mod treasury;
+pub mod attestation_nonce;
+pub mod user_stats;
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::TaskTraderError;
+
+/// Edge of the referral graph: who invited `user`. Stored once per user at
+/// `[b"referral", user]`, so deeper referral levels are found by following
+/// `inviter` from one `Referral` to the next.
+#[account]
+pub struct Referral {
+    pub user: Pubkey,
+    pub inviter: Pubkey,
+}
+
+impl Referral {
+    pub const INIT_SPACE: usize = 8 + 32 + 32;
+
+    pub fn address(user: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"referral", user.as_ref()], &crate::ID).0
+    }
+
+    /// Reads the `[b"referral", user]` account. An empty account proves that
+    /// `user` has no inviter; any other address is rejected.
+    pub fn load(account: &AccountInfo, user: &Pubkey) -> Result<Option<Self>> {
+        if account.key() != Self::address(user) {
+            return Err(TaskTraderError::InvalidInviter.into());
+        }
+        if *account.owner != crate::ID || account.data_is_empty() {
+            return Ok(None);
+        }
+        let data = account.try_borrow_data()?;
+        Ok(Some(Self::try_deserialize(&mut &data[..])?))
+    }
+}
@@ -1,13 +1,33 @@
 use anchor_lang::prelude::*;
 
+#[derive(Clone, Copy, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct CoinFeeOverride {
+    pub coin_mint: Pubkey,
+    pub task_fee_bps: Option<u16>,
+    pub reward_fee_bps: Option<u16>,
+}
+
+impl CoinFeeOverride {
+    pub const SPACE: usize = 32 + (1 + 2) + (1 + 2);
+}
+
 #[account]
 pub struct SupportCoin {
     pub coin_mints: Vec<Pubkey>,
+    pub fee_overrides: Vec<CoinFeeOverride>,
 }
 
 impl SupportCoin {
     pub const MAX_COINS: usize = 10;
     pub const INIT_SPACE: usize = 8 + // discriminator
         4 + // Vec length
-        (32 * Self::MAX_COINS); // Space for MAX_COINS Pubkeys
+        (32 * Self::MAX_COINS) + // Space for MAX_COINS Pubkeys
+        4 + // Vec length
+        (CoinFeeOverride::SPACE * Self::MAX_COINS); // One fee override per supported coin
+
+    pub fn fee_override(&self, coin_mint: &Pubkey) -> Option<&CoinFeeOverride> {
+        self.fee_overrides
+            .iter()
+            .find(|fee_override| fee_override.coin_mint == *coin_mint)
+    }
 }
@@ -1,20 +1,163 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::TaskTraderError;
+
 #[derive(Clone, Copy, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
 pub enum ApplicationState {
     Applied = 0,              // Applied
     AcceptedByAcceptance = 1, // Verification Passed
     Withdrawed = 2,           // Withdrawed
+    Rejected = 3,             // Rejected by a reviewer
+    Disputed = 4,             // Escalated to the arbiter, share frozen
+    Submitted = 5,            // Work submitted, awaiting review
 }
 
 #[account]
 pub struct TaskApplication {
     pub task_id: u64,
     pub applicant: Pubkey,
+    /// Inviter registered in the applicant's referral when applying, and the
+    /// only one its rewards go to. `Pubkey::default()` without one
     pub inviter: Pubkey,
     pub state: ApplicationState,
+    pub rejection_reason: Option<u16>,
+    pub approved_milestones: u8,
+    pub paid_milestones: u8,
+    pub awarded_amount: Option<u64>,
+    pub accepted_at: i64,
+    /// Payout received so far, after fees
+    pub claimed_amount: u64,
+    /// Hash of the latest deliverable submitted with `submit_work`
+    pub work_hash: [u8; 32],
+    /// Where that deliverable can be fetched
+    pub work_uri: String,
+    /// Until when rejected work may be disputed; 0 once the window lapsed
+    pub dispute_deadline: i64,
 }
 
 impl TaskApplication {
-    pub const INIT_SPACE: usize = 8 + 8 + 32 + 32 + 1;
+    pub const MAX_WORK_URI_LEN: usize = 200;
+    /// How long rejected work stays disputable (7 days)
+    pub const DISPUTE_WINDOW: i64 = 7 * 24 * 60 * 60;
+    /// Space without a work URI; `submit_work` reallocates to fit the URI.
+    pub const INIT_SPACE: usize = 8 + 8 + 32 + 32 + 1 + 1 + 2 + 1 + 1 + 1 + 8 + 8 + 8 + 32 + 4 + 8;
+
+    pub fn space(work_uri_len: usize) -> usize {
+        Self::INIT_SPACE + work_uri_len
+    }
+
+    /// Approves the next milestone. Milestones are approved strictly in order.
+    pub fn approve_milestone(&mut self, index: u8, milestone_count: u8) -> Result<()> {
+        if index != self.approved_milestones || index >= milestone_count {
+            return Err(TaskTraderError::InvalidMilestone.into());
+        }
+        self.approved_milestones += 1;
+        Ok(())
+    }
+
+    /// Whether the applicant delivered work that was not accepted and has not
+    /// given up or lapsed its dispute. These are the applications counted in
+    /// `TaskInfo::disputable_count`.
+    pub fn is_disputable(&self) -> bool {
+        match self.state {
+            ApplicationState::Submitted => true,
+            ApplicationState::Rejected => !self.work_uri.is_empty() && self.dispute_deadline != 0,
+            _ => false,
+        }
+    }
+
+    /// Whether the applicant may escalate its work to the arbiter at `now`.
+    /// Rejected work can only be disputed within the dispute window.
+    pub fn can_dispute(&self, now: i64) -> bool {
+        self.is_disputable()
+            && (self.state == ApplicationState::Submitted || now <= self.dispute_deadline)
+    }
+
+    /// Whether the dispute window of rejected work has passed at `now`.
+    pub fn is_dispute_lapsed(&self, now: i64) -> bool {
+        self.state == ApplicationState::Rejected
+            && self.is_disputable()
+            && now > self.dispute_deadline
+    }
+
+    /// Reads an application passed outside the typed accounts, e.g. through
+    /// `remaining_accounts`. Returns `None` unless `account` is a writable
+    /// application of task `task_id` owned by this program.
+    pub fn try_load(account: &AccountInfo, task_id: u64) -> Option<Self> {
+        if *account.owner != crate::ID || !account.is_writable {
+            return None;
+        }
+        let data = account.try_borrow_data().ok()?;
+        let task_application = Self::try_deserialize(&mut &data[..]).ok()?;
+        (task_application.task_id == task_id).then_some(task_application)
+    }
+
+    /// Writes an application read with `try_load` back to its account.
+    pub fn store(&self, account: &AccountInfo) -> Result<()> {
+        let mut data = account.try_borrow_mut_data()?;
+        self.try_serialize(&mut &mut data[..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected_work(dispute_deadline: i64) -> TaskApplication {
+        TaskApplication {
+            task_id: 1,
+            applicant: Pubkey::new_unique(),
+            inviter: Pubkey::default(),
+            state: ApplicationState::Rejected,
+            rejection_reason: None,
+            approved_milestones: 0,
+            paid_milestones: 0,
+            awarded_amount: None,
+            accepted_at: 0,
+            claimed_amount: 0,
+            work_hash: [1; 32],
+            work_uri: "ipfs://deliverable".to_string(),
+            dispute_deadline,
+        }
+    }
+
+    #[test]
+    fn rejected_work_is_disputable_within_the_window() {
+        let application = rejected_work(1_000);
+        assert!(application.can_dispute(1_000));
+        assert!(!application.is_dispute_lapsed(1_000));
+    }
+
+    #[test]
+    fn rejected_work_lapses_after_the_window() {
+        let application = rejected_work(1_000);
+        assert!(!application.can_dispute(1_001));
+        assert!(application.is_dispute_lapsed(1_001));
+        // Still counted as disputable until the lapse is recorded
+        assert!(application.is_disputable());
+    }
+
+    #[test]
+    fn lapsed_work_is_no_longer_disputable() {
+        let application = rejected_work(0);
+        assert!(!application.is_disputable());
+        assert!(!application.can_dispute(0));
+        assert!(!application.is_dispute_lapsed(i64::MAX));
+    }
+
+    #[test]
+    fn submitted_work_has_no_window() {
+        let mut application = rejected_work(0);
+        application.state = ApplicationState::Submitted;
+        assert!(application.can_dispute(i64::MAX));
+        assert!(!application.is_dispute_lapsed(i64::MAX));
+    }
+
+    #[test]
+    fn rejection_without_work_is_not_disputable() {
+        let mut application = rejected_work(1_000);
+        application.work_uri.clear();
+        assert!(!application.can_dispute(0));
+        assert!(!application.is_dispute_lapsed(i64::MAX));
+    }
 }
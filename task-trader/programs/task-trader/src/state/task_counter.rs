@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::TaskTraderError;
+
+/// Global allocator for task IDs, stored at `[b"task_counter"]`. Tasks keep
+/// their `[b"task_info", task_id]` address, so clients derive the next task
+/// from `next_task_id`.
+#[account]
+pub struct TaskCounter {
+    pub next_task_id: u64,
+}
+
+impl TaskCounter {
+    pub const INIT_SPACE: usize = 8 + 8;
+
+    /// Hands out the current ID and advances the counter.
+    pub fn allocate(&mut self) -> Result<u64> {
+        let task_id = self.next_task_id;
+        self.next_task_id = task_id
+            .checked_add(1)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(task_id)
+    }
+}
@@ -1,4 +1,100 @@
 use anchor_lang::prelude::*;
+
+use crate::{errors::TaskTraderError, utils::merkle_utils};
+
+#[derive(Clone, Copy, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum TaskState {
+    Open = 0,      // Accepting applications and reviews
+    Paused = 1,    // Temporarily halted by the requester
+    Closed = 2,    // Closed by the requester, unclaimed escrow refunded
+    Cancelled = 3, // Cancelled before any acceptance, escrow refunded
+}
+
+/// Requires applicants to hold an SBT minted by `sbt-minter`.
+#[derive(Clone, Copy, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct SbtGate {
+    pub min_score: u64,
+    /// Also require the inviter, when one is given, to hold a qualifying SBT.
+    pub require_inviter_sbt: bool,
+}
+
+impl SbtGate {
+    pub const SPACE: usize = 8 + 1;
+}
+
+/// Unlocks an accepted application's payout linearly over `duration` seconds
+/// from its acceptance. Nothing can be claimed before `cliff` seconds passed.
+#[derive(Clone, Copy, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct Vesting {
+    pub cliff: i64,
+    pub duration: i64,
+}
+
+impl Vesting {
+    pub const SPACE: usize = 8 + 8;
+
+    /// Portion of `total` unlocked at `now` for an application accepted at `start`.
+    pub fn vested_amount(&self, total: u64, start: i64, now: i64) -> Result<u64> {
+        let elapsed = now.saturating_sub(start);
+        if elapsed < self.cliff {
+            return Ok(0);
+        }
+        if elapsed >= self.duration {
+            return Ok(total);
+        }
+        let vested = (total as u128)
+            .checked_mul(elapsed as u128)
+            .map(|amount| amount / self.duration as u128)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(vested as u64)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum TaskCategory {
+    Development = 0,
+    Design = 1,
+    Writing = 2,
+    Marketing = 3,
+    Community = 4,
+    Research = 5,
+    Other = 6,
+}
+
+/// What frontends need to render a task. The full description and
+/// requirements live at `uri`, whose content hashes to `content_hash`.
+#[derive(Clone, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct TaskMetadata {
+    pub title: String,
+    pub uri: String,
+    pub content_hash: [u8; 32],
+    pub category: TaskCategory,
+    pub tags: Vec<String>,
+}
+
+impl TaskMetadata {
+    pub const MAX_TITLE_LEN: usize = 64;
+    pub const MAX_URI_LEN: usize = 200;
+    pub const MAX_TAGS: usize = 5;
+    pub const MAX_TAG_LEN: usize = 16;
+    pub const SPACE: usize = 4 + Self::MAX_TITLE_LEN // title
+        + 4 + Self::MAX_URI_LEN // uri
+        + 32 // content hash
+        + 1 // category
+        + 4 + (4 + Self::MAX_TAG_LEN) * Self::MAX_TAGS; // tags
+
+    pub fn is_valid(&self) -> bool {
+        !self.title.is_empty()
+            && self.title.len() <= Self::MAX_TITLE_LEN
+            && self.uri.len() <= Self::MAX_URI_LEN
+            && self.tags.len() <= Self::MAX_TAGS
+            && self
+                .tags
+                .iter()
+                .all(|tag| !tag.is_empty() && tag.len() <= Self::MAX_TAG_LEN)
+    }
+}
+
 #[account]
 pub struct TaskInfo {
     pub task_id: u64,
@@ -7,8 +103,320 @@ pub struct TaskInfo {
     pub coin_mint: Pubkey,
     pub rewards: u64,
     pub requester: Pubkey,
+    pub state: TaskState,
+    pub applied_count: u64,
+    pub accepted_count: u64,
+    pub paid_count: u64,
+    pub apply_deadline: i64,
+    pub complete_deadline: i64,
+    pub escrow_amount: u64,
+    pub allowed_applicants: Vec<Pubkey>,
+    pub allowlist_root: Option<[u8; 32]>,
+    pub reviewers: Vec<Pubkey>,
+    pub milestones: Vec<u64>,
+    pub sbt_gate: Option<SbtGate>,
+    pub vesting: Option<Vesting>,
+    pub metadata: TaskMetadata,
+    /// Applications with submitted work and no acceptance, which may still dispute
+    pub disputable_count: u64,
 }
 
 impl TaskInfo {
-    pub const INIT_SPACE: usize = 8 + 8 + 8 + 32 + 8 + 8 + 8 + 32;
+    pub const MAX_ALLOWED_APPLICANTS: usize = 10;
+    pub const MAX_REVIEWERS: usize = 5;
+    pub const MAX_MILESTONES: usize = 10;
+    pub const INIT_SPACE: usize = 8 + 8 + 8 + 32 + 8 + 8 + 8 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 8
+        + 4 + (32 * Self::MAX_ALLOWED_APPLICANTS) // allowed applicants
+        + 1 + 32 // allowlist root
+        + 4 + (32 * Self::MAX_REVIEWERS) // reviewers
+        + 4 + (8 * Self::MAX_MILESTONES) // milestones
+        + 1 + SbtGate::SPACE // sbt gate
+        + 1 + Vesting::SPACE // vesting
+        + TaskMetadata::SPACE // metadata
+        + 8; // disputable count
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.state, TaskState::Open | TaskState::Paused)
+    }
+
+    /// Applications are accepted up to and including the application deadline.
+    pub fn is_apply_expired(&self, now: i64) -> bool {
+        now > self.apply_deadline
+    }
+
+    /// Reviews are accepted up to and including the completion deadline.
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.complete_deadline
+    }
+
+    /// Replaces the allowlist. An empty list without a root makes the task public.
+    pub fn set_allowlist(
+        &mut self,
+        allowed_applicants: Vec<Pubkey>,
+        allowlist_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        if allowed_applicants.len() > Self::MAX_ALLOWED_APPLICANTS {
+            return Err(TaskTraderError::TooManyAllowedApplicants.into());
+        }
+        self.allowed_applicants = allowed_applicants;
+        self.allowlist_root = allowlist_root;
+        Ok(())
+    }
+
+    /// An applicant may apply when the task is public, when they are listed
+    /// explicitly, or when they prove membership in the allowlist Merkle tree.
+    pub fn is_applicant_allowed(&self, applicant: &Pubkey, proof: Option<&[[u8; 32]]>) -> bool {
+        if self.allowed_applicants.is_empty() && self.allowlist_root.is_none() {
+            return true;
+        }
+        if self.allowed_applicants.contains(applicant) {
+            return true;
+        }
+        match (&self.allowlist_root, proof) {
+            (Some(root), Some(proof)) => {
+                merkle_utils::verify_proof(proof, root, merkle_utils::applicant_leaf(applicant))
+            }
+            _ => false,
+        }
+    }
+
+    pub fn set_metadata(&mut self, metadata: TaskMetadata) -> Result<()> {
+        if !metadata.is_valid() {
+            return Err(TaskTraderError::InvalidMetadata.into());
+        }
+        self.metadata = metadata;
+        Ok(())
+    }
+
+    pub fn set_reviewers(&mut self, reviewers: Vec<Pubkey>) -> Result<()> {
+        if reviewers.len() > Self::MAX_REVIEWERS {
+            return Err(TaskTraderError::TooManyReviewers.into());
+        }
+        self.reviewers = reviewers;
+        Ok(())
+    }
+
+    /// Sets the ordered milestone amounts. An empty list pays the whole task
+    /// amount as a single milestone.
+    pub fn set_milestones(&mut self, milestones: Vec<u64>) -> Result<()> {
+        if milestones.len() > Self::MAX_MILESTONES {
+            return Err(TaskTraderError::InvalidMilestones.into());
+        }
+        if !milestones.is_empty() {
+            let total = milestones.iter().try_fold(0u64, |total, amount| {
+                if *amount == 0 {
+                    return None;
+                }
+                total.checked_add(*amount)
+            });
+            if total != Some(self.task_amount) {
+                return Err(TaskTraderError::InvalidMilestones.into());
+            }
+        }
+        self.milestones = milestones;
+        Ok(())
+    }
+
+    /// Sets the vesting schedule. Vesting spreads a single payout over time, so
+    /// it cannot be combined with milestones.
+    pub fn set_vesting(&mut self, vesting: Option<Vesting>) -> Result<()> {
+        if let Some(vesting) = vesting {
+            if vesting.duration <= 0
+                || vesting.cliff < 0
+                || vesting.cliff > vesting.duration
+                || !self.milestones.is_empty()
+            {
+                return Err(TaskTraderError::InvalidVesting.into());
+            }
+        }
+        self.vesting = vesting;
+        Ok(())
+    }
+
+    pub fn milestone_count(&self) -> u8 {
+        self.milestones.len().max(1) as u8
+    }
+
+    pub fn milestone_amount(&self, index: u8) -> Result<u64> {
+        if self.milestones.is_empty() && index == 0 {
+            return Ok(self.task_amount);
+        }
+        self.milestones
+            .get(index as usize)
+            .copied()
+            .ok_or(TaskTraderError::InvalidMilestone.into())
+    }
+
+    /// Applications can be reviewed by the requester, by a reviewer the
+    /// requester delegated to, or by the platform admin as an override.
+    pub fn is_authorized_reviewer(&self, reviewer: &Pubkey, admin_signer: &Pubkey) -> bool {
+        self.requester == *reviewer
+            || self.reviewers.contains(reviewer)
+            || *admin_signer == *reviewer
+    }
+
+    pub fn record_application(&mut self) -> Result<()> {
+        self.applied_count = self
+            .applied_count
+            .checked_add(1)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(())
+    }
+
+    /// Forgets an application whose account is closed, whether cancelled
+    /// before review or reclaimed once paid out or rejected.
+    pub fn revert_application(&mut self) -> Result<()> {
+        self.applied_count = self
+            .applied_count
+            .checked_sub(1)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(())
+    }
+
+    /// Reserves one of the `taker_num` seats for an accepted application.
+    pub fn record_acceptance(&mut self) -> Result<()> {
+        if self.accepted_count >= self.taker_num {
+            return Err(TaskTraderError::TakerNumExceeded.into());
+        }
+        self.accepted_count = self
+            .accepted_count
+            .checked_add(1)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(())
+    }
+
+    /// Releases the seat of an acceptance that was reverted before payout.
+    pub fn revert_acceptance(&mut self) -> Result<()> {
+        self.accepted_count = self
+            .accepted_count
+            .checked_sub(1)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(())
+    }
+
+    pub fn record_payout(&mut self) -> Result<()> {
+        self.paid_count = self
+            .paid_count
+            .checked_add(1)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(())
+    }
+
+    /// Records tokens deposited into the task vault.
+    pub fn add_escrow(&mut self, amount: u64) -> Result<()> {
+        self.escrow_amount = self
+            .escrow_amount
+            .checked_add(amount)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(())
+    }
+
+    /// Records tokens leaving the task vault so `escrow_amount` always matches its balance.
+    pub fn release_escrow(&mut self, amount: u64) -> Result<()> {
+        self.escrow_amount = self
+            .escrow_amount
+            .checked_sub(amount)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(())
+    }
+
+    /// Escrow deposited for a single taker: the task amount plus the inviter rewards.
+    pub fn amount_per_taker(&self) -> Result<u64> {
+        self.task_amount
+            .checked_add(self.rewards)
+            .ok_or(TaskTraderError::NumericalOverflow.into())
+    }
+
+    /// Seats that have not been paid out yet, whether accepted or still open.
+    pub fn unpaid_seats(&self) -> Result<u64> {
+        self.taker_num
+            .checked_sub(self.paid_count)
+            .ok_or(TaskTraderError::NumericalOverflow.into())
+    }
+
+    /// Tracks an application moving into or out of the disputable states.
+    pub fn record_disputable(&mut self, was_disputable: bool, is_disputable: bool) -> Result<()> {
+        self.disputable_count = match (was_disputable, is_disputable) {
+            (false, true) => self.disputable_count.checked_add(1),
+            (true, false) => self.disputable_count.checked_sub(1),
+            _ => Some(self.disputable_count),
+        }
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(())
+    }
+
+    /// Seats that must stay available: the accepted ones plus one per
+    /// application that may still dispute.
+    pub fn reserved_seats(&self) -> Result<u64> {
+        self.accepted_count
+            .checked_add(self.disputable_count)
+            .ok_or(TaskTraderError::NumericalOverflow.into())
+    }
+
+    /// Gives up every seat nobody was accepted for except `kept_seats` of them
+    /// and returns the escrow of the seats given up.
+    pub fn release_open_seats(&mut self, kept_seats: u64) -> Result<u64> {
+        let open_seats = self
+            .taker_num
+            .checked_sub(self.accepted_count)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        let released_seats = open_seats.saturating_sub(kept_seats);
+        self.taker_num -= released_seats;
+        released_seats
+            .checked_mul(self.amount_per_taker()?)
+            .ok_or(TaskTraderError::NumericalOverflow.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VESTING: Vesting = Vesting {
+        cliff: 100,
+        duration: 1_000,
+    };
+
+    #[test]
+    fn nothing_vests_before_the_cliff() {
+        assert_eq!(VESTING.vested_amount(1_000_000, 500, 500).unwrap(), 0);
+        assert_eq!(VESTING.vested_amount(1_000_000, 500, 599).unwrap(), 0);
+    }
+
+    #[test]
+    fn vests_linearly_after_the_cliff() {
+        assert_eq!(VESTING.vested_amount(1_000_000, 500, 600).unwrap(), 100_000);
+        assert_eq!(
+            VESTING.vested_amount(1_000_000, 500, 1_000).unwrap(),
+            500_000
+        );
+        // 999 * 333 / 1_000 = 332.667
+        assert_eq!(VESTING.vested_amount(999, 500, 833).unwrap(), 332);
+    }
+
+    #[test]
+    fn fully_vested_after_the_duration() {
+        assert_eq!(
+            VESTING.vested_amount(1_000_000, 500, 1_500).unwrap(),
+            1_000_000
+        );
+        assert_eq!(
+            VESTING.vested_amount(1_000_000, 500, i64::MAX).unwrap(),
+            1_000_000
+        );
+    }
+
+    #[test]
+    fn nothing_vests_before_the_start() {
+        assert_eq!(VESTING.vested_amount(1_000_000, 500, 0).unwrap(), 0);
+        assert_eq!(VESTING.vested_amount(1_000_000, 500, i64::MIN).unwrap(), 0);
+    }
+
+    #[test]
+    fn large_totals_do_not_overflow() {
+        assert_eq!(
+            VESTING.vested_amount(u64::MAX, 0, 500).unwrap(),
+            u64::MAX / 2
+        );
+    }
 }
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::TaskTraderError;
+
+/// Protocol fees of one mint, held in the `[b"treasury_vault", coin_mint]`
+/// token account until the admin collects them. Stored at
+/// `[b"treasury", coin_mint]`, which is also the vault's authority.
+#[account]
+pub struct Treasury {
+    pub coin_mint: Pubkey,
+    /// Fees held in the vault that have not been collected yet
+    pub accrued_fees: u64,
+    /// Lifetime total swept out by `collect_fees`
+    pub collected_fees: u64,
+    pub bump: u8,
+}
+
+impl Treasury {
+    pub const INIT_SPACE: usize = 8 + 32 + 8 + 8 + 1;
+
+    /// Records fees that arrived in the treasury vault.
+    pub fn accrue(&mut self, amount: u64) -> Result<()> {
+        self.accrued_fees = self
+            .accrued_fees
+            .checked_add(amount)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(())
+    }
+
+    /// Marks every accrued fee as collected and returns the collected amount.
+    pub fn collect(&mut self) -> Result<u64> {
+        let amount = self.accrued_fees;
+        self.collected_fees = self
+            .collected_fees
+            .checked_add(amount)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        self.accrued_fees = 0;
+        Ok(amount)
+    }
+}
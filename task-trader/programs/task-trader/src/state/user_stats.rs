@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::TaskTraderError, state::task_application::ApplicationState};
+
+/// On-chain reputation of one wallet in one mint, stored at
+/// `[b"user_stats", wallet, coin_mint]`. Amounts are in base units of
+/// `coin_mint`; totals across mints are summed off-chain.
+#[account]
+pub struct UserStats {
+    pub wallet: Pubkey,
+    pub coin_mint: Pubkey,
+    /// Tasks created as requester
+    pub tasks_posted: u64,
+    /// Escrow deposited when creating those tasks
+    pub total_escrowed: u64,
+    /// Applications whose current review is an acceptance
+    pub applications_accepted: u64,
+    /// Rejections received, kept even when the work is resubmitted or later accepted
+    pub applications_rejected: u64,
+    /// Accepted applications paid out in full, and disputes settled in the worker's favor
+    pub tasks_completed: u64,
+    /// Payouts received as worker, after fees
+    pub total_earned: u64,
+    pub bump: u8,
+}
+
+impl UserStats {
+    pub const INIT_SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    /// Sets the identity of a freshly created (or existing) stats account.
+    pub fn initialize(&mut self, wallet: Pubkey, coin_mint: Pubkey, bump: u8) {
+        self.wallet = wallet;
+        self.coin_mint = coin_mint;
+        self.bump = bump;
+    }
+
+    pub fn record_task_posted(&mut self, escrow_amount: u64) -> Result<()> {
+        self.tasks_posted = self
+            .tasks_posted
+            .checked_add(1)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        self.total_escrowed = self
+            .total_escrowed
+            .checked_add(escrow_amount)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        Ok(())
+    }
+
+    /// Records an application moving from its `previous` state to its
+    /// `current` one. Acceptances count while they stand, and their decrement
+    /// saturates since applications reviewed before stats existed were never
+    /// counted. Rejections are a running total that leaving `Rejected` keeps.
+    pub fn record_review(
+        &mut self,
+        previous: ApplicationState,
+        current: ApplicationState,
+    ) -> Result<()> {
+        if previous == current {
+            return Ok(());
+        }
+        if previous == ApplicationState::AcceptedByAcceptance {
+            self.applications_accepted = self.applications_accepted.saturating_sub(1);
+        }
+        match current {
+            ApplicationState::AcceptedByAcceptance => {
+                self.applications_accepted = self
+                    .applications_accepted
+                    .checked_add(1)
+                    .ok_or(TaskTraderError::NumericalOverflow)?
+            }
+            ApplicationState::Rejected => {
+                self.applications_rejected = self
+                    .applications_rejected
+                    .checked_add(1)
+                    .ok_or(TaskTraderError::NumericalOverflow)?
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Records a payout of `amount`; `is_final_payout` completes the task.
+    pub fn record_payout(&mut self, amount: u64, is_final_payout: bool) -> Result<()> {
+        self.total_earned = self
+            .total_earned
+            .checked_add(amount)
+            .ok_or(TaskTraderError::NumericalOverflow)?;
+        if is_final_payout {
+            self.tasks_completed = self
+                .tasks_completed
+                .checked_add(1)
+                .ok_or(TaskTraderError::NumericalOverflow)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a stats account passed through `remaining_accounts`. Returns
+    /// `None` unless `account` is the writable stats of `wallet` in `coin_mint`.
+    pub fn try_load(account: &AccountInfo, wallet: &Pubkey, coin_mint: &Pubkey) -> Option<Self> {
+        if *account.owner != crate::ID || !account.is_writable {
+            return None;
+        }
+        let data = account.try_borrow_data().ok()?;
+        let user_stats = Self::try_deserialize(&mut &data[..]).ok()?;
+        (user_stats.wallet == *wallet && user_stats.coin_mint == *coin_mint).then_some(user_stats)
+    }
+
+    /// Writes stats read with `try_load` back to their account.
+    pub fn store(&self, account: &AccountInfo) -> Result<()> {
+        let mut data = account.try_borrow_mut_data()?;
+        self.try_serialize(&mut &mut data[..])
+    }
+}
@@ -0,0 +1,134 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{ed25519_program, instruction::Instruction},
+};
+
+/// Size of one entry of the Ed25519 program's offsets table.
+const OFFSETS_SIZE: usize = 14;
+/// Offsets table starts after `num_signatures` and a padding byte.
+const OFFSETS_START: usize = 2;
+const PUBKEY_SIZE: usize = 32;
+const SIGNATURE_SIZE: usize = 64;
+
+fn read_u16(data: &[u8], offset: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+}
+
+/// Whether `instruction` is an Ed25519 program instruction that verifies
+/// exactly one signature of `signer` over `message`.
+///
+/// The runtime rejects the transaction if the signature itself is invalid, so
+/// only the signed pubkey and message have to be matched here. All offsets
+/// must point into `instruction` itself; references to other instructions are
+/// rejected so the checked bytes are the verified ones.
+pub fn verifies_signature(instruction: &Instruction, signer: &Pubkey, message: &[u8]) -> bool {
+    if instruction.program_id != ed25519_program::ID || !instruction.accounts.is_empty() {
+        return false;
+    }
+    let data = &instruction.data;
+    if data.first() != Some(&1) || data.len() < OFFSETS_START + OFFSETS_SIZE {
+        return false;
+    }
+
+    let field = |index: usize| read_u16(data, OFFSETS_START + 2 * index);
+    let (
+        Some(signature_offset),
+        Some(signature_instruction),
+        Some(pubkey_offset),
+        Some(pubkey_instruction),
+        Some(message_offset),
+        Some(message_size),
+        Some(message_instruction),
+    ) = (
+        field(0),
+        field(1),
+        field(2),
+        field(3),
+        field(4),
+        field(5),
+        field(6),
+    )
+    else {
+        return false;
+    };
+    let current_instruction = u16::MAX as usize;
+    if signature_instruction != current_instruction
+        || pubkey_instruction != current_instruction
+        || message_instruction != current_instruction
+    {
+        return false;
+    }
+
+    data.get(signature_offset..signature_offset + SIGNATURE_SIZE)
+        .is_some()
+        && data.get(pubkey_offset..pubkey_offset + PUBKEY_SIZE) == Some(signer.as_ref())
+        && data.get(message_offset..message_offset + message_size) == Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ed25519 instruction in the layout produced by the web3.js and SDK helpers.
+    fn ed25519_instruction(signer: &Pubkey, message: &[u8], instruction_index: u16) -> Instruction {
+        let pubkey_offset = OFFSETS_START + OFFSETS_SIZE;
+        let signature_offset = pubkey_offset + PUBKEY_SIZE;
+        let message_offset = signature_offset + SIGNATURE_SIZE;
+
+        let mut data = vec![1u8, 0];
+        for value in [
+            signature_offset as u16,
+            instruction_index,
+            pubkey_offset as u16,
+            instruction_index,
+            message_offset as u16,
+            message.len() as u16,
+            instruction_index,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(signer.as_ref());
+        data.extend_from_slice(&[7u8; SIGNATURE_SIZE]);
+        data.extend_from_slice(message);
+
+        Instruction {
+            program_id: ed25519_program::ID,
+            accounts: Vec::new(),
+            data,
+        }
+    }
+
+    #[test]
+    fn matches_signer_and_message() {
+        let signer = Pubkey::new_unique();
+        let instruction = ed25519_instruction(&signer, b"attestation", u16::MAX);
+
+        assert!(verifies_signature(&instruction, &signer, b"attestation"));
+        assert!(!verifies_signature(
+            &instruction,
+            &Pubkey::new_unique(),
+            b"attestation"
+        ));
+        assert!(!verifies_signature(&instruction, &signer, b"attestation!"));
+    }
+
+    #[test]
+    fn rejects_foreign_instructions_and_references() {
+        let signer = Pubkey::new_unique();
+
+        // Offsets pointing at another instruction of the transaction
+        let instruction = ed25519_instruction(&signer, b"attestation", 0);
+        assert!(!verifies_signature(&instruction, &signer, b"attestation"));
+
+        // Same data under another program
+        let mut instruction = ed25519_instruction(&signer, b"attestation", u16::MAX);
+        instruction.program_id = Pubkey::new_unique();
+        assert!(!verifies_signature(&instruction, &signer, b"attestation"));
+
+        // Truncated data
+        let mut instruction = ed25519_instruction(&signer, b"attestation", u16::MAX);
+        instruction.data.truncate(OFFSETS_START + OFFSETS_SIZE + 8);
+        assert!(!verifies_signature(&instruction, &signer, b"attestation"));
+    }
+}
@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::TaskTraderError;
+
+/// Fees are expressed in basis points: 1 bps = 0.01%.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Hard ceiling for any configured fee rate (10%).
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+pub fn validate_fee_bps(fee_bps: u16) -> Result<()> {
+    if fee_bps > MAX_FEE_BPS {
+        return Err(TaskTraderError::InvalidFeeBps.into());
+    }
+    Ok(())
+}
+
+/// Fee charged on `amount`, rounded down so the payee is never charged more
+/// than the configured rate.
+pub fn calculate_fee(amount: u64, fee_bps: u16) -> Result<u64> {
+    validate_fee_bps(fee_bps)?;
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .and_then(|product| product.checked_div(BPS_DENOMINATOR as u128))
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+    u64::try_from(fee).map_err(|_| TaskTraderError::NumericalOverflow.into())
+}
+
+/// Splits `amount` into `(fee, amount_after_fee)`; both parts always sum to `amount`.
+pub fn split_fee(amount: u64, fee_bps: u16) -> Result<(u64, u64)> {
+    let fee = calculate_fee(amount, fee_bps)?;
+    let amount_after_fee = amount
+        .checked_sub(fee)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+    Ok((fee, amount_after_fee))
+}
+
+/// Referral splits are shares of the task rewards and may add up to at most 100%.
+pub fn validate_referral_splits(splits_bps: &[u16]) -> Result<()> {
+    let total: u64 = splits_bps.iter().map(|split_bps| *split_bps as u64).sum();
+    if total > BPS_DENOMINATOR {
+        return Err(TaskTraderError::InvalidReferralSplits.into());
+    }
+    Ok(())
+}
+
+/// Share of `rewards` for each referral level, rounded down. Whatever is not
+/// handed out, including the shares of missing levels, stays with the caller.
+pub fn split_referral_rewards(rewards: u64, splits_bps: &[u16]) -> Result<Vec<u64>> {
+    validate_referral_splits(splits_bps)?;
+    splits_bps
+        .iter()
+        .map(|split_bps| {
+            let share = (rewards as u128) * (*split_bps as u128) / (BPS_DENOMINATOR as u128);
+            u64::try_from(share).map_err(|_| TaskTraderError::NumericalOverflow.into())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_amount_or_zero_rate_has_no_fee() {
+        assert_eq!(calculate_fee(0, MAX_FEE_BPS).unwrap(), 0);
+        assert_eq!(calculate_fee(1_000_000, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn exact_division() {
+        assert_eq!(calculate_fee(10_000, 1).unwrap(), 1);
+        assert_eq!(calculate_fee(1_000_000, 10).unwrap(), 1_000);
+        assert_eq!(calculate_fee(1_000_000, MAX_FEE_BPS).unwrap(), 100_000);
+    }
+
+    #[test]
+    fn rounds_down() {
+        // 9_999 * 1 / 10_000 = 0.9999
+        assert_eq!(calculate_fee(9_999, 1).unwrap(), 0);
+        // 10_001 * 1 / 10_000 = 1.0001
+        assert_eq!(calculate_fee(10_001, 1).unwrap(), 1);
+        // 19_999 * 1 / 10_000 = 1.9999
+        assert_eq!(calculate_fee(19_999, 1).unwrap(), 1);
+        // 333 * 30 / 10_000 = 0.999
+        assert_eq!(calculate_fee(333, 30).unwrap(), 0);
+        // 334 * 30 / 10_000 = 1.002
+        assert_eq!(calculate_fee(334, 30).unwrap(), 1);
+        // 1 * 1_000 / 10_000 = 0.1
+        assert_eq!(calculate_fee(1, MAX_FEE_BPS).unwrap(), 0);
+        // 9 * 1_000 / 10_000 = 0.9
+        assert_eq!(calculate_fee(9, MAX_FEE_BPS).unwrap(), 0);
+        // 10 * 1_000 / 10_000 = 1
+        assert_eq!(calculate_fee(10, MAX_FEE_BPS).unwrap(), 1);
+    }
+
+    #[test]
+    fn matches_legacy_per_mille_rates() {
+        // The former `fee_ratio` was per mille: 1 per mille == 10 bps
+        for amount in [0u64, 1, 999, 1_000, 1_001, 123_456_789] {
+            assert_eq!(calculate_fee(amount, 10).unwrap(), amount / 1000);
+        }
+    }
+
+    #[test]
+    fn does_not_overflow_on_large_amounts() {
+        let fee = calculate_fee(u64::MAX, MAX_FEE_BPS).unwrap();
+        assert_eq!(fee, u64::MAX / 10);
+        let (fee, rest) = split_fee(u64::MAX, MAX_FEE_BPS).unwrap();
+        assert_eq!(fee + rest, u64::MAX);
+    }
+
+    #[test]
+    fn split_always_sums_to_amount() {
+        for amount in [0u64, 1, 7, 99, 10_000, 10_001, 987_654_321] {
+            for fee_bps in [0u16, 1, 10, 25, 333, MAX_FEE_BPS] {
+                let (fee, rest) = split_fee(amount, fee_bps).unwrap();
+                assert_eq!(fee + rest, amount);
+                assert!(fee <= amount / 10);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_rates_above_maximum() {
+        assert!(validate_fee_bps(MAX_FEE_BPS).is_ok());
+        assert!(validate_fee_bps(MAX_FEE_BPS + 1).is_err());
+        assert!(calculate_fee(1_000, MAX_FEE_BPS + 1).is_err());
+        assert!(split_fee(1_000, u16::MAX).is_err());
+    }
+
+    #[test]
+    fn referral_splits_cannot_exceed_rewards() {
+        assert!(validate_referral_splits(&[7_000, 2_000, 1_000]).is_ok());
+        assert!(validate_referral_splits(&[]).is_ok());
+        assert!(validate_referral_splits(&[7_000, 2_000, 1_001]).is_err());
+        assert!(split_referral_rewards(1_000, &[10_000, 1]).is_err());
+    }
+
+    #[test]
+    fn referral_shares_round_down() {
+        assert_eq!(
+            split_referral_rewards(1_001, &[7_000, 2_000, 1_000]).unwrap(),
+            vec![700, 200, 100]
+        );
+        assert_eq!(
+            split_referral_rewards(u64::MAX, &[10_000]).unwrap(),
+            vec![u64::MAX]
+        );
+        assert_eq!(split_referral_rewards(999, &[5_000]).unwrap(), vec![499]);
+    }
+}
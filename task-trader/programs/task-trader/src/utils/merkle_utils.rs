@@ -0,0 +1,65 @@
+use anchor_lang::{prelude::*, solana_program::keccak::hashv as keccak};
+
+/// Leaf of the applicant allowlist tree: `keccak(applicant)`.
+pub fn applicant_leaf(applicant: &Pubkey) -> [u8; 32] {
+    keccak(&[applicant.as_ref()]).0
+}
+
+/// Verifies a Merkle proof where each parent is `keccak(min(a, b) || max(a, b))`,
+/// so proofs do not need to carry left/right positions.
+pub fn verify_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            keccak(&[node.as_ref(), sibling.as_ref()]).0
+        } else {
+            keccak(&[sibling.as_ref(), node.as_ref()]).0
+        }
+    });
+    computed == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parent(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        if a <= b {
+            keccak(&[a.as_ref(), b.as_ref()]).0
+        } else {
+            keccak(&[b.as_ref(), a.as_ref()]).0
+        }
+    }
+
+    #[test]
+    fn single_leaf_tree_has_empty_proof() {
+        let leaf = applicant_leaf(&Pubkey::new_unique());
+        assert!(verify_proof(&[], &leaf, leaf));
+    }
+
+    #[test]
+    fn verifies_every_leaf_of_a_four_leaf_tree() {
+        let leaves: Vec<[u8; 32]> = (0..4)
+            .map(|_| applicant_leaf(&Pubkey::new_unique()))
+            .collect();
+        let left = parent(leaves[0], leaves[1]);
+        let right = parent(leaves[2], leaves[3]);
+        let root = parent(left, right);
+
+        assert!(verify_proof(&[leaves[1], right], &root, leaves[0]));
+        assert!(verify_proof(&[leaves[0], right], &root, leaves[1]));
+        assert!(verify_proof(&[leaves[3], left], &root, leaves[2]));
+        assert!(verify_proof(&[leaves[2], left], &root, leaves[3]));
+    }
+
+    #[test]
+    fn rejects_foreign_leaf_and_wrong_proof() {
+        let a = applicant_leaf(&Pubkey::new_unique());
+        let b = applicant_leaf(&Pubkey::new_unique());
+        let root = parent(a, b);
+        let outsider = applicant_leaf(&Pubkey::new_unique());
+
+        assert!(!verify_proof(&[b], &root, outsider));
+        assert!(!verify_proof(&[a], &root, a));
+        assert!(!verify_proof(&[], &root, a));
+    }
+}
@@ -1 +1,5 @@
 pub mod token_utils;
+pub mod merkle_utils;
+pub mod fee_utils;
+pub mod sbt_utils;
+pub mod ed25519_utils;
@@ -0,0 +1,148 @@
+use anchor_lang::{prelude::*, solana_program::hash::hash};
+
+/// The `sbt-minter` program, owner of every `SbtInfo` account.
+pub const SBT_MINTER_PROGRAM_ID: Pubkey = pubkey!("GwvQ53QTu1xz3XXYfG5m5jEqwhMBvVBudPS8TUuFYnhT");
+
+/// Mirror of sbt-minter's `SbtInfo` account layout, read without a crate dependency.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SbtInfo {
+    pub name: String,
+    pub photo: String,
+    pub twitter_id: String,
+    pub discord_id: String,
+    pub telegram_id: String,
+    pub sol_fee: u64,
+    pub usd_fee: u64,
+    pub mai_fee: u64,
+    pub score: u64,
+    pub minted: bool,
+}
+
+pub fn sbt_info_address(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"sbt_info", wallet.as_ref()], &SBT_MINTER_PROGRAM_ID).0
+}
+
+fn sbt_info_discriminator() -> [u8; 8] {
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(b"account:SbtInfo").to_bytes()[..8]);
+    discriminator
+}
+
+/// Whether `sbt_info` is the `[b"sbt_info", wallet]` account of sbt-minter and
+/// records a minted SBT with at least `min_score`.
+pub fn holds_sbt(sbt_info: &AccountInfo, wallet: &Pubkey, min_score: u64) -> bool {
+    if *sbt_info.owner != SBT_MINTER_PROGRAM_ID || *sbt_info.key != sbt_info_address(wallet) {
+        return false;
+    }
+    let Ok(data) = sbt_info.try_borrow_data() else {
+        return false;
+    };
+    if data.len() < 8 || data[..8] != sbt_info_discriminator() {
+        return false;
+    }
+    match SbtInfo::deserialize(&mut &data[8..]) {
+        Ok(info) => info.minted && info.score >= min_score,
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sbt_info_data(score: u64, minted: bool) -> Vec<u8> {
+        let mut data = sbt_info_discriminator().to_vec();
+        SbtInfo {
+            name: "name".to_string(),
+            photo: String::new(),
+            twitter_id: String::new(),
+            discord_id: String::new(),
+            telegram_id: String::new(),
+            sol_fee: 0,
+            usd_fee: 0,
+            mai_fee: 0,
+            score,
+            minted,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        data
+    }
+
+    fn check(
+        wallet: &Pubkey,
+        key: Pubkey,
+        owner: Pubkey,
+        mut data: Vec<u8>,
+        min_score: u64,
+    ) -> bool {
+        let mut lamports = 0;
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        holds_sbt(&account, wallet, min_score)
+    }
+
+    #[test]
+    fn accepts_minted_sbt_with_enough_score() {
+        let wallet = Pubkey::new_unique();
+        let key = sbt_info_address(&wallet);
+        assert!(check(
+            &wallet,
+            key,
+            SBT_MINTER_PROGRAM_ID,
+            sbt_info_data(50, true),
+            50
+        ));
+        assert!(!check(
+            &wallet,
+            key,
+            SBT_MINTER_PROGRAM_ID,
+            sbt_info_data(49, true),
+            50
+        ));
+        assert!(!check(
+            &wallet,
+            key,
+            SBT_MINTER_PROGRAM_ID,
+            sbt_info_data(50, false),
+            0
+        ));
+    }
+
+    #[test]
+    fn rejects_foreign_accounts() {
+        let wallet = Pubkey::new_unique();
+        let key = sbt_info_address(&wallet);
+        // Someone else's SbtInfo
+        let other = sbt_info_address(&Pubkey::new_unique());
+        assert!(!check(
+            &wallet,
+            other,
+            SBT_MINTER_PROGRAM_ID,
+            sbt_info_data(1, true),
+            0
+        ));
+        // Right address, wrong owner
+        assert!(!check(
+            &wallet,
+            key,
+            Pubkey::new_unique(),
+            sbt_info_data(1, true),
+            0
+        ));
+        // Wrong discriminator
+        let mut data = sbt_info_data(1, true);
+        data[0] ^= 0xff;
+        assert!(!check(&wallet, key, SBT_MINTER_PROGRAM_ID, data, 0));
+        // Uninitialized
+        assert!(!check(&wallet, key, SBT_MINTER_PROGRAM_ID, Vec::new(), 0));
+    }
+}
@@ -1,57 +1,215 @@
 use {
-    anchor_lang::prelude::*,
-    anchor_spl::token::{self, Transfer},
+    crate::errors::TaskTraderError,
+    anchor_lang::{prelude::*, system_program},
+    anchor_spl::{
+        token::spl_token,
+        token_2022::spl_token_2022::{
+            self,
+            extension::{
+                transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+            },
+            onchain::invoke_transfer_checked,
+        },
+        token_interface::{self, CloseAccount, Mint, SyncNative, TokenAccount},
+    },
 };
 
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
+/// Amount that has to be sent so that `net_amount` arrives after the mint's
+/// Token-2022 transfer fee. Mints without a `TransferFeeConfig` charge nothing.
+pub fn gross_up_transfer_fee(mint: &AccountInfo, net_amount: u64) -> Result<u64> {
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let fee = match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(fee_config) => fee_config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, net_amount)
+            .ok_or(TaskTraderError::NumericalOverflow)?,
+        Err(_) => 0,
+    };
+
+    Ok(net_amount
+        .checked_add(fee)
+        .ok_or(TaskTraderError::NumericalOverflow)?)
+}
+
+/// Amount that arrives when `amount` is sent, after the mint's Token-2022
+/// transfer fee is withheld. Mints without a `TransferFeeConfig` charge nothing.
+pub fn net_of_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let fee = match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(fee_config) => fee_config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(TaskTraderError::NumericalOverflow)?,
+        Err(_) => 0,
+    };
+
+    Ok(amount
+        .checked_sub(fee)
+        .ok_or(TaskTraderError::NumericalOverflow)?)
+}
+
+/// `transfer_checked` through either token program. `additional_accounts` carries
+/// the extra accounts required by transfer-hook mints and may be empty.
+/// Hook forwarding is unverified: the tests deploy no transfer-hook program.
+#[allow(clippy::too_many_arguments)]
 pub fn transfer_token<'info>(
     token_program: AccountInfo<'info>,
     from_account: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
     to_account: AccountInfo<'info>,
     authority: AccountInfo<'info>,
+    additional_accounts: &[AccountInfo<'info>],
     task_amount: u64,
+    decimals: u8,
 ) -> Result<()> {
-    let cpi_ctx = CpiContext::new(
+    transfer_token_with_singer(
         token_program,
-        Transfer {
-            from: from_account,
-            to: to_account,
-            authority: authority,
-        },
-    );
-
-    msg!("Transferring tokens...");
-    msg!("Amount: {}", task_amount);
-
-    token::transfer(cpi_ctx, task_amount)?;
-    msg!("Transfer completed");
-
-    Ok(())
+        from_account,
+        mint,
+        to_account,
+        authority,
+        additional_accounts,
+        task_amount,
+        decimals,
+        None,
+    )
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn transfer_token_with_singer<'info>(
     token_program: AccountInfo<'info>,
     from_account: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
     to_account: AccountInfo<'info>,
     authority: AccountInfo<'info>,
+    additional_accounts: &[AccountInfo<'info>],
     task_amount: u64,
+    decimals: u8,
     signer_seeds: Option<&[&[&[u8]]]>,
 ) -> Result<()> {
-    let seeds = signer_seeds.unwrap_or(&[&[]]);
-    let cpi_ctx = CpiContext::new_with_signer(
-        token_program,
-        Transfer {
-            from: from_account,
-            to: to_account,
-            authority: authority,
-        },
-        seeds,
-    );
+    let seeds = signer_seeds.unwrap_or(&[]);
 
     msg!("Transferring tokens...");
     msg!("Amount: {}", task_amount);
 
-    token::transfer(cpi_ctx, task_amount)?;
+    invoke_transfer_checked(
+        token_program.key,
+        from_account,
+        mint,
+        to_account,
+        authority,
+        additional_accounts,
+        task_amount,
+        decimals,
+        seeds,
+    )?;
     msg!("Transfer completed");
 
     Ok(())
 }
+
+/// Moves a deposit of `net_amount` from `depositor_account` into `vault`,
+/// grossed up so the vault receives it in full despite the mint's transfer fee.
+/// Native SOL is escrowed as wSOL: whatever the depositor does not already hold
+/// wrapped is wrapped first. Returns what the vault actually received.
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_to_vault<'info>(
+    system_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    depositor: AccountInfo<'info>,
+    depositor_account: &InterfaceAccount<'info, TokenAccount>,
+    vault: &mut InterfaceAccount<'info, TokenAccount>,
+    additional_accounts: &[AccountInfo<'info>],
+    net_amount: u64,
+) -> Result<u64> {
+    let transfer_amount = gross_up_transfer_fee(&mint.to_account_info(), net_amount)?;
+
+    if is_native_mint(&mint.key()) {
+        let wrap_amount = transfer_amount.saturating_sub(depositor_account.amount);
+        if wrap_amount > 0 {
+            wrap_sol(
+                system_program,
+                token_program.clone(),
+                depositor.clone(),
+                depositor_account.to_account_info(),
+                wrap_amount,
+            )?;
+        }
+    }
+
+    let vault_amount_before = vault.amount;
+    transfer_token(
+        token_program,
+        depositor_account.to_account_info(),
+        mint.to_account_info(),
+        vault.to_account_info(),
+        depositor,
+        additional_accounts,
+        transfer_amount,
+        mint.decimals,
+    )?;
+
+    vault.reload()?;
+    let received_amount = vault
+        .amount
+        .checked_sub(vault_amount_before)
+        .ok_or(TaskTraderError::NumericalOverflow)?;
+    if received_amount < net_amount {
+        return Err(TaskTraderError::InvalidAmount.into());
+    }
+    Ok(received_amount)
+}
+
+/// Wraps `amount` lamports from `from` into the wSOL token account `to`.
+pub fn wrap_sol<'info>(
+    system_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    msg!("Wrapping SOL...");
+    msg!("Amount: {}", amount);
+
+    system_program::transfer(
+        CpiContext::new(
+            system_program,
+            system_program::Transfer {
+                from,
+                to: to.clone(),
+            },
+        ),
+        amount,
+    )?;
+    token_interface::sync_native(CpiContext::new(token_program, SyncNative { account: to }))?;
+    msg!("Wrap completed");
+
+    Ok(())
+}
+
+/// Unwraps a wSOL token account by closing it, sending all its lamports to `destination`.
+pub fn unwrap_sol<'info>(
+    token_program: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    destination: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+) -> Result<()> {
+    msg!("Unwrapping SOL...");
+
+    token_interface::close_account(CpiContext::new(
+        token_program,
+        CloseAccount {
+            account,
+            destination,
+            authority,
+        },
+    ))?;
+    msg!("Unwrap completed");
+
+    Ok(())
+}
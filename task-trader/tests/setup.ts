@@ -20,13 +20,10 @@ export interface TestContext {
   program: Program<TaskTrader>;
   wallet: Keypair;
   admin: PublicKey;
-  poolAuthority: PublicKey;
   usdtMint: PublicKey;
   mai3Mint: PublicKey;
   userUsdtAccount: PublicKey;
   userMai3Account: PublicKey;
-  poolUsdtAccount: PublicKey;
-  poolMai3Account: PublicKey;
   applicant: Keypair;
 }
 
@@ -48,10 +45,6 @@ export async function getTestContext(): Promise<TestContext> {
     [Buffer.from("admin")],
     program.programId
   );
-  const [poolAuthority] = PublicKey.findProgramAddressSync(
-    [Buffer.from("pool_authority")],
-    program.programId
-  );
 
   // Create test applicant
   const applicant = Keypair.generate();
@@ -112,16 +105,6 @@ export async function getTestContext(): Promise<TestContext> {
     ASSOCIATED_TOKEN_PROGRAM_ID
   );
 
-  const poolUsdtAccount = await anchor.utils.token.associatedAddress({
-    mint: usdtMint,
-    owner: poolAuthority,
-  });
-
-  const poolMai3Account = await anchor.utils.token.associatedAddress({
-    mint: mai3Mint,
-    owner: poolAuthority,
-  });
-
   // Mint initial tokens
   await mintTo(
     provider.connection,
@@ -143,7 +126,7 @@ export async function getTestContext(): Promise<TestContext> {
 
   // Initialize admin
   await program.methods
-    .initialize(wallet.publicKey, wallet.publicKey, new anchor.BN(1))
+    .initialize(wallet.publicKey, 10, 10, wallet.publicKey)
     .accounts({
       payer: wallet.publicKey,
       admin: admin,
@@ -152,18 +135,36 @@ export async function getTestContext(): Promise<TestContext> {
     })
     .rpc();
 
+  // Initialize the fee treasuries of the test coins
+  for (const coinMint of [usdtMint, mai3Mint]) {
+    await program.methods
+      .initializeTreasury()
+      .accounts({
+        payer: wallet.publicKey,
+        coinMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+  }
+
+  // Initialize the task ID allocator
+  await program.methods
+    .initializeTaskCounter(new anchor.BN(1))
+    .accounts({
+      payer: wallet.publicKey,
+      admin: admin,
+    })
+    .rpc();
+
   context = {
     provider,
     program,
     wallet,
     admin,
-    poolAuthority,
     usdtMint,
     mai3Mint,
     userUsdtAccount,
     userMai3Account,
-    poolUsdtAccount,
-    poolMai3Account,
     applicant,
   };
 
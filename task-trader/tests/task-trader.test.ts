@@ -1,50 +1,91 @@
 import * as anchor from "@coral-xyz/anchor";
 import {
+  Ed25519Program,
   Keypair,
   PublicKey,
   SystemProgram,
   SYSVAR_RENT_PUBKEY,
+  Transaction,
+  sendAndConfirmTransaction,
 } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
+  NATIVE_MINT,
+  ExtensionType,
   createAssociatedTokenAccount,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
   getAssociatedTokenAddressSync,
+  getMintLen,
+  mintTo,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { createHash } from "crypto";
 import { getTestContext } from "./setup";
 import { TaskTrader } from "../target/types/task_trader";
 
 describe("Task Trader", () => {
   let context: Awaited<ReturnType<typeof getTestContext>>;
+  let usdtTaskInfo: PublicKey;
+  let unacceptedTaskInfo: PublicKey;
 
   before(async () => {
     context = await getTestContext();
   });
 
+  // Task IDs are allocated by the program, so the next task's address comes
+  // from the counter
+  async function nextTaskInfo(program: anchor.Program<TaskTrader>) {
+    const [taskCounter] = PublicKey.findProgramAddressSync(
+      [Buffer.from("task_counter")],
+      program.programId
+    );
+    const { nextTaskId } = await program.account.taskCounter.fetch(
+      taskCounter
+    );
+    const [taskInfo] = PublicKey.findProgramAddressSync(
+      [Buffer.from("task_info"), nextTaskId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    return { taskId: nextTaskId.toNumber(), taskInfo };
+  }
+
+  type TaskMetadata = anchor.IdlTypes<TaskTrader>["taskMetadata"];
+
+  function taskMetadata(title: string, tags: string[] = []): TaskMetadata {
+    const uri = `ipfs://${title.toLowerCase().replace(/ /g, "-")}`;
+    return {
+      title,
+      uri,
+      contentHash: Array.from(createHash("sha256").update(uri).digest()),
+      category: { development: {} },
+      tags,
+    };
+  }
+
   async function createTask(
     program: anchor.Program<TaskTrader>,
     params: {
-      taskId: number;
       taskAmount: number;
       takerNum: number;
       coinMint: PublicKey;
       rewards: number;
       expireTime: number;
+      applyDeadline?: number;
+      allowedApplicants?: PublicKey[];
       wallet: Keypair;
       admin: PublicKey;
-      poolAuthority: PublicKey;
       userCoinAccount: PublicKey;
-      poolCoinAccount: PublicKey;
+      tokenProgram?: PublicKey;
+      milestones?: number[];
+      sbtGate?: { minScore: anchor.BN; requireInviterSbt: boolean };
+      vesting?: { cliff: anchor.BN; duration: anchor.BN };
+      metadata?: TaskMetadata;
     }
   ) {
-    const [taskInfo] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("task_info"),
-        new anchor.BN(params.taskId).toArrayLike(Buffer, "le", 8),
-      ],
-      program.programId
-    );
+    const { taskInfo } = await nextTaskInfo(program);
 
     const [supportCoin] = PublicKey.findProgramAddressSync(
       [Buffer.from("support_coin")],
@@ -53,26 +94,29 @@ describe("Task Trader", () => {
 
     await program.methods
       .createTask(
-        new anchor.BN(params.taskId),
         new anchor.BN(params.taskAmount),
         new anchor.BN(params.takerNum),
-        params.coinMint,
-        new anchor.BN(params.rewards)
+        new anchor.BN(params.rewards),
+        new anchor.BN(params.applyDeadline ?? params.expireTime),
+        new anchor.BN(params.expireTime),
+        params.allowedApplicants ?? [],
+        null,
+        (params.milestones ?? []).map((amount) => new anchor.BN(amount)),
+        params.sbtGate ?? null,
+        params.vesting ?? null,
+        params.metadata ?? taskMetadata("Test task")
       )
       .accounts({
         user: params.wallet.publicKey,
         admin: params.admin,
-        poolAuthority: params.poolAuthority,
         taskInfo: taskInfo,
         coinMint: params.coinMint,
         userCoinAccount: params.userCoinAccount,
-        poolCoinAccount: params.poolCoinAccount,
         supportCoin: supportCoin,
-        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenProgram: params.tokenProgram ?? TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
         rent: SYSVAR_RENT_PUBKEY,
-        feeReceiver: params.wallet.publicKey,
       })
       .signers([params.wallet])
       .rpc();
@@ -80,21 +124,69 @@ describe("Task Trader", () => {
     return taskInfo;
   }
 
+  // A funded applicant whose `[referral, applicant]` account names `inviter`
+  async function referredApplicant(
+    program: anchor.Program<TaskTrader>,
+    inviter: PublicKey
+  ) {
+    const invitee = Keypair.generate();
+    await program.provider.connection.confirmTransaction(
+      await program.provider.connection.requestAirdrop(
+        invitee.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+    await program.methods
+      .registerReferral(inviter)
+      .accounts({
+        user: invitee.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([invitee])
+      .rpc();
+    return invitee;
+  }
+
+  function referralAddress(
+    program: anchor.Program<TaskTrader>,
+    user: PublicKey
+  ) {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("referral"), user.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  async function submitWork(
+    program: anchor.Program<TaskTrader>,
+    taskInfo: PublicKey,
+    applicant: Keypair,
+    workUri = "ipfs://deliverable"
+  ) {
+    await program.methods
+      .submitWork(
+        Array.from(createHash("sha256").update(workUri).digest()),
+        workUri
+      )
+      .accounts({ taskInfo, applicant: applicant.publicKey })
+      .signers([applicant])
+      .rpc();
+  }
+
   describe("Admin", () => {
     it("Initialize admin account", async () => {
       const { program, admin, wallet, applicant } = context;
 
       const adminAccount = await program.account.admin.fetch(admin);
       assert.ok(adminAccount.signer.equals(wallet.publicKey));
-      assert.ok(adminAccount.feeReceiver.equals(wallet.publicKey));
 
       const updateAdmin = async function (
         publicKey: PublicKey,
         signer: Keypair,
-        fee_ratio: number
+        feeBps: number
       ) {
         return program.methods
-          .updateAdmin(publicKey, publicKey, new anchor.BN(fee_ratio))
+          .updateAdmin(publicKey, feeBps, feeBps, publicKey)
           .accounts({
             payer: signer.publicKey,
             admin: admin,
@@ -103,19 +195,61 @@ describe("Task Trader", () => {
           .rpc();
       };
       try {
-        await updateAdmin(applicant.publicKey, applicant, 1);
+        await updateAdmin(applicant.publicKey, applicant, 10);
         assert.fail("Should have failed when update admin for non-admin");
       } catch (error) {
         assert.include(error.message, "Unauthorized");
       }
 
-      await updateAdmin(applicant.publicKey, wallet, 1);
+      await updateAdmin(applicant.publicKey, wallet, 10);
 
       const adminAccount2 = await program.account.admin.fetch(admin);
       assert.ok(adminAccount2.signer.equals(applicant.publicKey));
-      assert.ok(adminAccount2.feeReceiver.equals(applicant.publicKey));
 
-      await updateAdmin(wallet.publicKey, applicant, 1);
+      await updateAdmin(wallet.publicKey, applicant, 10);
+    });
+
+    it("Should reject fee rates above the maximum", async () => {
+      const { program, admin, wallet } = context;
+
+      try {
+        await program.methods
+          .updateAdmin(wallet.publicKey, 1001, 10, wallet.publicKey)
+          .accounts({ payer: wallet.publicKey, admin })
+          .signers([wallet])
+          .rpc();
+        assert.fail("Should have failed when the fee exceeds the maximum");
+      } catch (error) {
+        assert.include(error.message, "InvalidFeeBps");
+      }
+
+      const adminAccount = await program.account.admin.fetch(admin);
+      assert.equal(adminAccount.taskFeeBps, 10);
+      assert.equal(adminAccount.rewardFeeBps, 10);
+    });
+
+    it("Should emit AdminUpdated through a self-CPI", async () => {
+      const { program, provider, admin, wallet } = context;
+
+      const signature = await program.methods
+        .updateAdmin(wallet.publicKey, 10, 10, wallet.publicKey)
+        .accounts({ payer: wallet.publicKey, admin })
+        .signers([wallet])
+        .rpc({ commitment: "confirmed" });
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const eventIx = tx.meta.innerInstructions[0].instructions[0];
+      const eventData = anchor.utils.bytes.bs58.decode(eventIx.data);
+      const event = program.coder.events.decode(
+        anchor.utils.bytes.base64.encode(eventData.subarray(8))
+      );
+
+      assert.equal(event.name, "adminUpdated");
+      assert.ok(event.data.signer.equals(wallet.publicKey));
+      assert.equal(event.data.taskFeeBps, 10);
     });
 
     it("Verify initial token balances", async () => {
@@ -180,6 +314,45 @@ describe("Task Trader", () => {
       });
     });
 
+    it("Should override fee rates per coin", async () => {
+      const { program, wallet, admin, mai3Mint } = context;
+
+      const [supportCoinPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("support_coin")],
+        program.programId
+      );
+
+      const updateOverride = (
+        taskFeeBps: number | null,
+        rewardFeeBps: number | null
+      ) =>
+        program.methods
+          .updateCoinFeeOverride(mai3Mint, taskFeeBps, rewardFeeBps)
+          .accounts({
+            payer: wallet.publicKey,
+            admin,
+            supportCoin: supportCoinPDA,
+          })
+          .signers([wallet])
+          .rpc();
+
+      await updateOverride(50, null);
+      let supportCoinAccount = await program.account.supportCoin.fetch(
+        supportCoinPDA
+      );
+      assert.equal(supportCoinAccount.feeOverrides.length, 1);
+      assert.ok(supportCoinAccount.feeOverrides[0].coinMint.equals(mai3Mint));
+      assert.equal(supportCoinAccount.feeOverrides[0].taskFeeBps, 50);
+      assert.isNull(supportCoinAccount.feeOverrides[0].rewardFeeBps);
+
+      // Clearing both rates removes the override
+      await updateOverride(null, null);
+      supportCoinAccount = await program.account.supportCoin.fetch(
+        supportCoinPDA
+      );
+      assert.equal(supportCoinAccount.feeOverrides.length, 0);
+    });
+
     it("Should fail when non-admin tries to update support coins", async () => {
       const { program, applicant, admin } = context;
 
@@ -220,16 +393,13 @@ describe("Task Trader", () => {
         program,
         wallet,
         admin,
-        poolAuthority,
         usdtMint,
         mai3Mint,
         userUsdtAccount,
         userMai3Account,
-        poolUsdtAccount,
-        poolMai3Account,
       } = context;
 
-      const taskId = 1;
+      const { taskId } = await nextTaskInfo(program);
       const taskAmount = 10_000_000; // 10 USDT
       const takerNum = 10;
       const rewards = 5_000_000; // 5 USDT
@@ -241,7 +411,6 @@ describe("Task Trader", () => {
         );
 
       const taskInfo = await createTask(program, {
-        taskId,
         taskAmount,
         takerNum,
         coinMint: usdtMint,
@@ -249,11 +418,11 @@ describe("Task Trader", () => {
         expireTime,
         wallet,
         admin,
-        poolAuthority,
         userCoinAccount: userUsdtAccount,
-        poolCoinAccount: poolUsdtAccount,
       });
 
+      usdtTaskInfo = taskInfo;
+
       // Verify task info
       const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
       assert.equal(taskInfoAccount.taskId.toNumber(), taskId);
@@ -277,30 +446,96 @@ describe("Task Trader", () => {
         "USDT not transferred correctly"
       );
 
-      const poolUsdtBalance =
-        await program.provider.connection.getTokenAccountBalance(
-          poolUsdtAccount
-        );
+      const [taskVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("task_vault"), taskInfo.toBuffer()],
+        program.programId
+      );
+      const taskVaultBalance =
+        await program.provider.connection.getTokenAccountBalance(taskVault);
       assert.equal(
-        poolUsdtBalance.value.amount,
+        taskVaultBalance.value.amount,
         expectedTransferAmount.toString(),
-        "Pool USDT balance incorrect"
+        "Task vault USDT balance incorrect"
+      );
+      assert.equal(
+        taskInfoAccount.escrowAmount.toNumber(),
+        expectedTransferAmount,
+        "Task escrow amount does not reconcile with the vault"
       );
+
+      // The first task posted in USDT creates the requester's stats
+      const [requesterStats] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("user_stats"),
+          wallet.publicKey.toBuffer(),
+          usdtMint.toBuffer(),
+        ],
+        program.programId
+      );
+      const stats = await program.account.userStats.fetch(requesterStats);
+      assert.equal(stats.tasksPosted.toNumber(), 1);
+      assert.equal(stats.totalEscrowed.toNumber(), expectedTransferAmount);
+    });
+
+    it("Should store and update task metadata", async () => {
+      const { program, wallet, admin, applicant, usdtMint, userUsdtAccount } =
+        context;
+
+      const taskInfo = await createTask(program, {
+        taskAmount: 1000,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin,
+        userCoinAccount: userUsdtAccount,
+        metadata: taskMetadata("Write docs", ["docs", "rust"]),
+      });
+      let taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.metadata.title, "Write docs");
+      assert.equal(taskInfoAccount.metadata.uri, "ipfs://write-docs");
+      assert.deepEqual(taskInfoAccount.metadata.tags, ["docs", "rust"]);
+
+      const update = (signer: Keypair, metadata: TaskMetadata) =>
+        program.methods
+          .updateTaskMetadata(metadata)
+          .accounts({ user: signer.publicKey, taskInfo })
+          .signers([signer])
+          .rpc();
+
+      try {
+        await update(applicant, taskMetadata("Hijacked"));
+        assert.fail("Should have failed when not the requester");
+      } catch (error) {
+        assert.include(error.message, "InvalidRequester");
+      }
+      try {
+        await update(
+          wallet,
+          taskMetadata("Too many tags", ["a", "b", "c", "d", "e", "f"])
+        );
+        assert.fail("Should have failed with too many tags");
+      } catch (error) {
+        assert.include(error.message, "InvalidMetadata");
+      }
+
+      await update(wallet, {
+        ...taskMetadata("Write better docs", ["docs"]),
+        category: { writing: {} },
+      });
+      taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.metadata.title, "Write better docs");
+      assert.deepEqual(taskInfoAccount.metadata.category, { writing: {} });
+      assert.deepEqual(taskInfoAccount.metadata.tags, ["docs"]);
     });
   });
 
   describe("Task Application", () => {
     it("Apply for a task", async () => {
       const { program, applicant } = context;
-      const taskId = 1;
-
-      const [taskInfo] = PublicKey.findProgramAddressSync(
-        [
-          Buffer.from("task_info"),
-          new anchor.BN(taskId).toArrayLike(Buffer, "le", 8),
-        ],
-        program.programId
-      );
+      const taskInfo = usdtTaskInfo;
+      const { taskId } = await program.account.taskInfo.fetch(taskInfo);
 
       const [taskApplication] = PublicKey.findProgramAddressSync(
         [
@@ -318,13 +553,19 @@ describe("Task Trader", () => {
           taskApplication: taskApplication,
           applicant: applicant.publicKey,
           systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
         })
         .signers([applicant])
         .rpc();
+      await submitWork(program, taskInfo, applicant);
 
       const taskApplicationAccount =
         await program.account.taskApplication.fetch(taskApplication);
-      assert.equal(taskApplicationAccount.taskId.toNumber(), taskId);
+      assert.equal(
+        taskApplicationAccount.taskId.toNumber(),
+        taskId.toNumber()
+      );
       assert.ok(taskApplicationAccount.applicant.equals(applicant.publicKey));
     });
   });
@@ -334,9 +575,7 @@ describe("Task Trader", () => {
       const { program, wallet, usdtMint } = context;
 
       // Get task info PDA
-      const taskId = 12;
       const taskInfo = await createTask(program, {
-        taskId,
         taskAmount: 1_000_000,
         takerNum: 1,
         coinMint: usdtMint,
@@ -344,9 +583,7 @@ describe("Task Trader", () => {
         expireTime: Math.floor(Date.now() / 1000) + 86400,
         wallet,
         admin: context.admin,
-        poolAuthority: context.poolAuthority,
         userCoinAccount: context.userUsdtAccount,
-        poolCoinAccount: context.poolUsdtAccount,
       });
 
       // Get task application PDA
@@ -366,13 +603,16 @@ describe("Task Trader", () => {
           taskApplication: taskApplication,
           applicant: context.applicant.publicKey,
           systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
         })
         .signers([context.applicant])
         .rpc();
+      await submitWork(program, taskInfo, context.applicant);
 
       // Now verify and accept the task application
       await program.methods
-        .verifyTaskApplication(true)
+        .verifyTaskApplication(true, null, null, null)
         .accounts({
           taskApplication,
           taskInfo,
@@ -394,9 +634,7 @@ describe("Task Trader", () => {
       const { program, wallet, usdtMint } = context;
 
       // Get task info PDA
-      const taskId = 13;
       const taskInfo = await createTask(program, {
-        taskId,
         taskAmount: 1_000_000,
         takerNum: 1,
         coinMint: usdtMint,
@@ -404,9 +642,7 @@ describe("Task Trader", () => {
         expireTime: Math.floor(Date.now() / 1000) + 86400,
         wallet,
         admin: context.admin,
-        poolAuthority: context.poolAuthority,
         userCoinAccount: context.userUsdtAccount,
-        poolCoinAccount: context.poolUsdtAccount,
       });
 
       // Get task application PDA
@@ -426,13 +662,15 @@ describe("Task Trader", () => {
           taskApplication: taskApplication,
           applicant: context.applicant.publicKey,
           systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
         })
         .signers([context.applicant])
         .rpc();
 
-      // Now verify and accept the task application
+      // Now verify and reject the task application
       await program.methods
-        .verifyTaskApplication(false)
+        .verifyTaskApplication(false, 7, null, null)
         .accounts({
           taskApplication,
           taskInfo,
@@ -446,17 +684,31 @@ describe("Task Trader", () => {
         taskApplication
       );
       assert.deepEqual(applicationData.state, {
-        applied: {},
+        rejected: {},
       });
+      assert.equal(applicationData.rejectionReason, 7);
+
+      // The applicant can reclaim the rent of a rejected application
+      await program.methods
+        .closeApplication()
+        .accounts({
+          applicant: context.applicant.publicKey,
+          taskInfo,
+          taskApplication,
+        })
+        .signers([context.applicant])
+        .rpc();
+      assert.isNull(
+        await program.provider.connection.getAccountInfo(taskApplication)
+      );
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.appliedCount.toNumber(), 0);
     });
 
-    it("Should fail when non-admin tries to verify application", async () => {
-      const { program, wallet, usdtMint } = context;
+    it("Should let an applicant cancel a pending application", async () => {
+      const { program, wallet, applicant, usdtMint } = context;
 
-      // Get task info PDA
-      const taskId = 14;
       const taskInfo = await createTask(program, {
-        taskId,
         taskAmount: 1_000_000,
         takerNum: 1,
         coinMint: usdtMint,
@@ -464,85 +716,205 @@ describe("Task Trader", () => {
         expireTime: Math.floor(Date.now() / 1000) + 86400,
         wallet,
         admin: context.admin,
-        poolAuthority: context.poolAuthority,
         userCoinAccount: context.userUsdtAccount,
-        poolCoinAccount: context.poolUsdtAccount,
       });
 
-      // Get task application PDA
       const [taskApplication] = PublicKey.findProgramAddressSync(
         [
           Buffer.from("task_application"),
           taskInfo.toBuffer(),
-          context.applicant.publicKey.toBuffer(),
+          applicant.publicKey.toBuffer(),
         ],
         program.programId
       );
-
       await program.methods
         .applyTask(null)
         .accounts({
-          taskInfo: taskInfo,
-          taskApplication: taskApplication,
-          applicant: context.applicant.publicKey,
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
           systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
         })
-        .signers([context.applicant])
+        .signers([applicant])
         .rpc();
 
-      // Now verify and accept the task application
       try {
         await program.methods
-          .verifyTaskApplication(false)
+          .closeApplication()
+          .accounts({ applicant: applicant.publicKey, taskInfo, taskApplication })
+          .signers([applicant])
+          .rpc();
+        assert.fail("Should have failed when closing a pending application");
+      } catch (error) {
+        assert.include(error.message, "InvalidApplicationState");
+      }
+
+      await program.methods
+        .cancelApplication()
+        .accounts({ applicant: applicant.publicKey, taskInfo, taskApplication })
+        .signers([applicant])
+        .rpc();
+
+      assert.isNull(
+        await program.provider.connection.getAccountInfo(taskApplication)
+      );
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.appliedCount.toNumber(), 0);
+    });
+
+    it("Should not accept more applications than taker num", async () => {
+      const { program, wallet, provider, applicant, usdtMint } = context;
+
+      const taskInfo = await createTask(program, {
+        taskAmount: 1_000_000,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin: context.admin,
+        userCoinAccount: context.userUsdtAccount,
+      });
+
+      const secondApplicant = Keypair.generate();
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          secondApplicant.publicKey,
+          anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+
+      const applications = [];
+      for (const taker of [applicant, secondApplicant]) {
+        const [taskApplication] = PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("task_application"),
+            taskInfo.toBuffer(),
+            taker.publicKey.toBuffer(),
+          ],
+          program.programId
+        );
+        await program.methods
+          .applyTask(null)
           .accounts({
-            taskApplication,
             taskInfo,
-            user: context.applicant.publicKey,
+            taskApplication,
+            applicant: taker.publicKey,
+            systemProgram: SystemProgram.programId,
+            applicantSbtInfo: null,
+            inviterSbtInfo: null,
           })
-          .signers([context.applicant])
+          .signers([taker])
           .rpc();
-        assert.fail(
-          "Should have failed when non-requester tries to verify application"
-        );
+        await submitWork(program, taskInfo, taker);
+        applications.push(taskApplication);
+      }
+
+      const verify = (taskApplication: PublicKey, isAccepted: boolean) =>
+        program.methods
+          .verifyTaskApplication(isAccepted, null, null, null)
+          .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+          .signers([wallet])
+          .rpc();
+
+      await verify(applications[0], true);
+      try {
+        await verify(applications[1], true);
+        assert.fail("Should have failed when all seats are taken");
       } catch (error) {
-        assert.include(error.message, "InvalidRequester");
+        assert.include(error.message, "TakerNumExceeded");
       }
+
+      // Reverting the first acceptance frees the seat again
+      await verify(applications[0], false);
+      await verify(applications[1], true);
+
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.appliedCount.toNumber(), 2);
+      assert.equal(taskInfoAccount.acceptedCount.toNumber(), 1);
+      assert.equal(taskInfoAccount.paidCount.toNumber(), 0);
     });
-  });
 
-  describe("Withdraw", () => {
-    it("Should withdraw USDT successfully", async () => {
-      const {
-        program,
-        applicant,
+    it("Should only let allowlisted applicants apply", async () => {
+      const { program, wallet, provider, applicant, usdtMint } = context;
+
+      const outsider = Keypair.generate();
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          outsider.publicKey,
+          anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+
+      const taskInfo = await createTask(program, {
+        taskAmount: 1_000_000,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        allowedApplicants: [applicant.publicKey],
         wallet,
-        admin,
-        poolAuthority,
-        usdtMint,
-        mai3Mint,
-        userUsdtAccount,
-        userMai3Account,
-        poolUsdtAccount,
-        poolMai3Account,
-      } = context;
-      const taskId = 20;
+        admin: context.admin,
+        userCoinAccount: context.userUsdtAccount,
+      });
+
+      const apply = (taker: Keypair) =>
+        program.methods
+          .applyTask(null)
+          .accounts({
+            taskInfo,
+            taskApplication: PublicKey.findProgramAddressSync(
+              [
+                Buffer.from("task_application"),
+                taskInfo.toBuffer(),
+                taker.publicKey.toBuffer(),
+              ],
+              program.programId
+            )[0],
+            applicant: taker.publicKey,
+            systemProgram: SystemProgram.programId,
+            applicantSbtInfo: null,
+            inviterSbtInfo: null,
+          })
+          .signers([taker])
+          .rpc();
+
+      try {
+        await apply(outsider);
+        assert.fail("Should have failed when applicant is not allowlisted");
+      } catch (error) {
+        assert.include(error.message, "ApplicantNotAllowed");
+      }
+      await apply(applicant);
+
+      // The requester can open the task up to the outsider as well
+      await program.methods
+        .updateTaskAllowlist([applicant.publicKey, outsider.publicKey], null)
+        .accounts({ user: wallet.publicKey, taskInfo })
+        .signers([wallet])
+        .rpc();
+      await apply(outsider);
+
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.allowedApplicants.length, 2);
+      assert.equal(taskInfoAccount.appliedCount.toNumber(), 2);
+    });
+
+    it("Should only accept applications with submitted work", async () => {
+      const { program, wallet, applicant, usdtMint } = context;
 
-      // Create task first
       const taskInfo = await createTask(program, {
-        taskId,
         taskAmount: 1000,
         takerNum: 1,
         coinMint: usdtMint,
-        rewards: 100,
-        expireTime: Math.floor(Date.now() / 1000) + 3600,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
         wallet,
-        admin,
-        poolAuthority,
-        userCoinAccount: userUsdtAccount,
-        poolCoinAccount: poolUsdtAccount,
+        admin: context.admin,
+        userCoinAccount: context.userUsdtAccount,
       });
-
-      // Get task application PDA
       const [taskApplication] = PublicKey.findProgramAddressSync(
         [
           Buffer.from("task_application"),
@@ -551,8 +923,6 @@ describe("Task Trader", () => {
         ],
         program.programId
       );
-
-      // Apply for task
       await program.methods
         .applyTask(null)
         .accounts({
@@ -560,15 +930,238 @@ describe("Task Trader", () => {
           taskApplication,
           applicant: applicant.publicKey,
           systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
         })
         .signers([applicant])
         .rpc();
 
-      await program.methods
-        .verifyTaskApplication(true)
-        .accounts({
-          taskApplication,
-          taskInfo,
+      const accept = () =>
+        program.methods
+          .verifyTaskApplication(true, null, null, null)
+          .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+          .signers([wallet])
+          .rpc();
+
+      try {
+        await accept();
+        assert.fail("Should have failed without submitted work");
+      } catch (error) {
+        assert.include(error.message, "InvalidApplicationState");
+      }
+      try {
+        await submitWork(program, taskInfo, applicant, "x".repeat(201));
+        assert.fail("Should have failed with an overlong work URI");
+      } catch (error) {
+        assert.include(error.message, "InvalidWorkUri");
+      }
+
+      await submitWork(program, taskInfo, applicant, "ipfs://draft");
+      let application = await program.account.taskApplication.fetch(
+        taskApplication
+      );
+      assert.deepEqual(application.state, { submitted: {} });
+      assert.equal(application.workUri, "ipfs://draft");
+      await accept();
+
+      // Accepted applications can still update their deliverable, growing the account
+      const finalUri = "ipfs://final-deliverable-with-a-longer-uri";
+      await submitWork(program, taskInfo, applicant, finalUri);
+      application = await program.account.taskApplication.fetch(
+        taskApplication
+      );
+      assert.deepEqual(application.state, { acceptedByAcceptance: {} });
+      assert.equal(application.workUri, finalUri);
+      assert.deepEqual(
+        Buffer.from(application.workHash),
+        createHash("sha256").update(finalUri).digest()
+      );
+    });
+
+    it("Should fail when non-admin tries to verify application", async () => {
+      const { program, wallet, usdtMint } = context;
+
+      // Get task info PDA
+      const taskInfo = await createTask(program, {
+        taskAmount: 1_000_000,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin: context.admin,
+        userCoinAccount: context.userUsdtAccount,
+      });
+
+      // Get task application PDA
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          context.applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo: taskInfo,
+          taskApplication: taskApplication,
+          applicant: context.applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([context.applicant])
+        .rpc();
+
+      // Now verify and accept the task application
+      try {
+        await program.methods
+          .verifyTaskApplication(false, null, null, null)
+          .accounts({
+            taskApplication,
+            taskInfo,
+            user: context.applicant.publicKey,
+          })
+          .signers([context.applicant])
+          .rpc();
+        assert.fail(
+          "Should have failed when non-requester tries to verify application"
+        );
+      } catch (error) {
+        assert.include(error.message, "InvalidReviewer");
+      }
+    });
+
+    it("Should let a delegated reviewer verify applications", async () => {
+      const { program, wallet, provider, applicant, usdtMint } = context;
+
+      const reviewer = Keypair.generate();
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          reviewer.publicKey,
+          anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+
+      const taskInfo = await createTask(program, {
+        taskAmount: 1_000_000,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin: context.admin,
+        userCoinAccount: context.userUsdtAccount,
+      });
+
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([applicant])
+        .rpc();
+      await submitWork(program, taskInfo, applicant);
+
+      const verify = () =>
+        program.methods
+          .verifyTaskApplication(true, null, null, null)
+          .accounts({ taskApplication, taskInfo, user: reviewer.publicKey })
+          .signers([reviewer])
+          .rpc();
+
+      try {
+        await verify();
+        assert.fail("Should have failed before the reviewer is delegated");
+      } catch (error) {
+        assert.include(error.message, "InvalidReviewer");
+      }
+
+      await program.methods
+        .updateTaskReviewers([reviewer.publicKey])
+        .accounts({ user: wallet.publicKey, taskInfo })
+        .signers([wallet])
+        .rpc();
+      await verify();
+
+      const applicationData = await program.account.taskApplication.fetch(
+        taskApplication
+      );
+      assert.deepEqual(applicationData.state, { acceptedByAcceptance: {} });
+    });
+  });
+
+  describe("Withdraw", () => {
+    it("Should withdraw USDT successfully", async () => {
+      const {
+        program,
+        applicant,
+        wallet,
+        admin,
+        usdtMint,
+        mai3Mint,
+        userUsdtAccount,
+        userMai3Account,
+      } = context;
+
+      // Create task first
+      const taskInfo = await createTask(program, {
+        taskAmount: 1000,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 100,
+        expireTime: Math.floor(Date.now() / 1000) + 3600,
+        wallet,
+        admin,
+        userCoinAccount: userUsdtAccount,
+      });
+
+      // Get task application PDA
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+
+      // Apply for task
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([applicant])
+        .rpc();
+      await submitWork(program, taskInfo, applicant);
+
+      await program.methods
+        .verifyTaskApplication(true, null, null, null)
+        .accounts({
+          taskApplication,
+          taskInfo,
           user: wallet.publicKey,
         })
         .signers([wallet])
@@ -597,19 +1190,21 @@ describe("Task Trader", () => {
           user: applicant.publicKey,
           taskApplication: taskApplication,
           taskInfo: taskInfo,
-          poolAuthority: poolAuthority,
           coinMint: usdtMint,
           userCoinAccount: applicantUsdtAccount,
+          requester: null,
+          requesterCoinAccount: null,
           inviter: null,
           inviterCoinAccount: null,
-          poolCoinAccount: poolUsdtAccount,
+          inviterReferral: null,
+          secondLevelCoinAccount: null,
+          secondLevelReferral: null,
+          thirdLevelCoinAccount: null,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: SYSVAR_RENT_PUBKEY,
           admin: admin,
-          feeReceiver: wallet.publicKey,
-          feeReceiverCoinAccount: userUsdtAccount,
         })
         .signers([applicant])
         .rpc();
@@ -631,7 +1226,7 @@ describe("Task Trader", () => {
         );
       assert.equal(
         parseInt(afterBalance.value.amount) +
-          (1000 * parseInt(adminAccount.feeRatio.toString())) / 1000 -
+          Math.floor((1000 * adminAccount.taskFeeBps) / 10000) -
           parseInt(beforeBalance.value.amount),
         1000
       );
@@ -640,13 +1235,10 @@ describe("Task Trader", () => {
     it("Should withdraw USDT with inviter successfully", async () => {
       const {
         program,
-        applicant,
         wallet,
         usdtMint,
         admin,
         userUsdtAccount,
-        poolAuthority,
-        poolUsdtAccount,
       } = context;
 
       // Create a new keypair for inviter
@@ -655,17 +1247,11 @@ describe("Task Trader", () => {
         inviter.publicKey,
         10 * anchor.web3.LAMPORTS_PER_SOL
       );
+      // The applicant registered the inviter as its referral
+      const applicant = await referredApplicant(program, inviter.publicKey);
 
-      // Create task info PDA
-      const taskId = 30;
-      // Create task first
-      const [taskInfo] = PublicKey.findProgramAddressSync(
-        [
-          Buffer.from("task_info"),
-          new anchor.BN(taskId).toArrayLike(Buffer, "le", 8),
-        ],
-        program.programId
-      );
+      // Task info PDA of the task created below
+      const { taskInfo } = await nextTaskInfo(program);
 
       // Create task application PDA
       const [taskApplication] = PublicKey.findProgramAddressSync(
@@ -695,7 +1281,6 @@ describe("Task Trader", () => {
 
       // Create task with rewards for inviter
       await createTask(program, {
-        taskId: taskId,
         taskAmount: 100,
         takerNum: 1,
         coinMint: usdtMint,
@@ -703,25 +1288,34 @@ describe("Task Trader", () => {
         expireTime: Math.floor(Date.now() / 1000) + 3600,
         wallet: wallet,
         admin: admin,
-        poolAuthority: poolAuthority,
         userCoinAccount: userUsdtAccount,
-        poolCoinAccount: poolUsdtAccount,
       });
 
       // Apply for task
       await program.methods
-        .applyTask(inviter.publicKey)
+        .applyTask(null)
         .accounts({
           taskInfo,
           taskApplication,
           applicant: applicant.publicKey,
           systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
         })
         .signers([applicant])
         .rpc();
+      // The inviter comes from the applicant's referral, not from the caller
+      const appliedApplication = await program.account.taskApplication.fetch(
+        taskApplication
+      );
+      assert.equal(
+        appliedApplication.inviter.toBase58(),
+        inviter.publicKey.toBase58()
+      );
+      await submitWork(program, taskInfo, applicant);
 
       await program.methods
-        .verifyTaskApplication(true)
+        .verifyTaskApplication(true, null, null, null)
         .accounts({
           taskApplication,
           taskInfo,
@@ -730,12 +1324,6 @@ describe("Task Trader", () => {
         .signers([wallet])
         .rpc();
 
-      // Get balances before withdraw
-      const beforeApplicantBalance =
-        await context.provider.connection.getTokenAccountBalance(
-          applicantUsdtAccount
-        );
-
       // Withdraw
       await program.methods
         .withdraw()
@@ -743,19 +1331,22 @@ describe("Task Trader", () => {
           user: applicant.publicKey,
           taskApplication: taskApplication,
           taskInfo: taskInfo,
-          poolAuthority: poolAuthority,
           coinMint: usdtMint,
           userCoinAccount: applicantUsdtAccount,
+          requester: null,
+          requesterCoinAccount: null,
           inviter: inviter.publicKey,
           inviterCoinAccount: inviterUsdtAccount,
-          poolCoinAccount: poolUsdtAccount,
+          // The inviter was not referred itself, which its empty referral account proves
+          inviterReferral: referralAddress(program, inviter.publicKey),
+          secondLevelCoinAccount: null,
+          secondLevelReferral: null,
+          thirdLevelCoinAccount: null,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
           admin: admin,
-          feeReceiver: wallet.publicKey,
-          feeReceiverCoinAccount: userUsdtAccount,
         })
         .signers([applicant])
         .rpc();
@@ -775,10 +1366,7 @@ describe("Task Trader", () => {
       // Verify applicant received task amount
       assert.equal(
         parseInt(afterApplicantBalance.value.amount) +
-          parseInt(
-            `${(100 * parseInt(adminAccount.feeRatio.toString())) / 1000}`
-          ) -
-          parseInt(beforeApplicantBalance.value.amount),
+          Math.floor((100 * adminAccount.taskFeeBps) / 10000),
         100
       );
 
@@ -786,38 +1374,24 @@ describe("Task Trader", () => {
       assert.equal(parseInt(afterInviterBalance.value.amount), 10);
     });
 
-    it("Should fail when application state is not AcceptedByAcceptance", async () => {
-      const {
-        program,
-        applicant,
-        wallet,
-        admin,
-        poolAuthority,
-        usdtMint,
-        mai3Mint,
-        userUsdtAccount,
-        userMai3Account,
-        poolUsdtAccount,
-        poolMai3Account,
-      } = context;
-      const taskId = 21;
+    it("Should pay the awarded amount and return the rest to the requester", async () => {
+      const { program, applicant, wallet, admin, usdtMint, userUsdtAccount } =
+        context;
 
-      // Create task first
+      const taskAmount = 1000;
+      const awardedAmount = 600;
+      const rewards = 100;
       const taskInfo = await createTask(program, {
-        taskId,
-        taskAmount: 1000,
+        taskAmount,
         takerNum: 1,
         coinMint: usdtMint,
-        rewards: 100,
+        rewards,
         expireTime: Math.floor(Date.now() / 1000) + 3600,
         wallet,
         admin,
-        poolAuthority,
         userCoinAccount: userUsdtAccount,
-        poolCoinAccount: poolUsdtAccount,
       });
 
-      // Get task application PDA
       const [taskApplication] = PublicKey.findProgramAddressSync(
         [
           Buffer.from("task_application"),
@@ -827,7 +1401,6 @@ describe("Task Trader", () => {
         program.programId
       );
 
-      // Apply for task
       await program.methods
         .applyTask(null)
         .accounts({
@@ -835,75 +1408,222 @@ describe("Task Trader", () => {
           taskApplication,
           applicant: applicant.publicKey,
           systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
         })
         .signers([applicant])
         .rpc();
+      await submitWork(program, taskInfo, applicant);
 
-      // Get applicant's USDT balance before withdrawal
-      const applicantUsdtAccount = await getAssociatedTokenAddressSync(
+      try {
+        await program.methods
+          .verifyTaskApplication(true, null, null, new anchor.BN(taskAmount + 1))
+          .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+          .signers([wallet])
+          .rpc();
+        assert.fail("Should have failed when awarding more than the task amount");
+      } catch (error) {
+        assert.include(error.message, "InvalidAmount");
+      }
+
+      await program.methods
+        .verifyTaskApplication(true, null, null, new anchor.BN(awardedAmount))
+        .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+        .signers([wallet])
+        .rpc();
+
+      const applicantUsdtAccount = getAssociatedTokenAddressSync(
         usdtMint,
         applicant.publicKey,
         true,
         TOKEN_PROGRAM_ID,
         ASSOCIATED_TOKEN_PROGRAM_ID
       );
+      const withdrawAccounts = {
+        user: applicant.publicKey,
+        taskApplication,
+        taskInfo,
+        coinMint: usdtMint,
+        userCoinAccount: applicantUsdtAccount,
+        requester: null,
+        requesterCoinAccount: null,
+        inviter: null,
+        inviterCoinAccount: null,
+        inviterReferral: null,
+        secondLevelCoinAccount: null,
+        secondLevelReferral: null,
+        thirdLevelCoinAccount: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+        admin,
+      };
+
       try {
-        // Try to withdraw without acceptance
         await program.methods
           .withdraw()
-          .accounts({
-            user: applicant.publicKey,
-            taskApplication: taskApplication,
-            taskInfo: taskInfo,
-            poolAuthority: poolAuthority,
-            coinMint: usdtMint,
-            userCoinAccount: applicantUsdtAccount,
-            inviter: null,
-            inviterCoinAccount: null,
-            poolCoinAccount: poolUsdtAccount,
-            tokenProgram: TOKEN_PROGRAM_ID,
-            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-            systemProgram: SystemProgram.programId,
-            rent: SYSVAR_RENT_PUBKEY,
-            admin: admin,
-            feeReceiver: wallet.publicKey,
-            feeReceiverCoinAccount: userUsdtAccount,
-          })
+          .accounts(withdrawAccounts)
           .signers([applicant])
           .rpc();
-        assert.fail(
-          "Should have failed when application state is not AcceptedByAcceptance"
-        );
+        assert.fail("Should have failed without the requester accounts");
       } catch (error) {
-        assert.include(error.message, "InvalidApplicationState");
+        assert.include(error.message, "InvalidRequester");
       }
-    });
 
-    it("Should fail when wrong applicant tries to withdraw", async () => {
-      const {
-        program,
-        applicant,
-        wallet,
-        admin,
-        poolAuthority,
-        usdtMint,
-        mai3Mint,
-        userUsdtAccount,
-        userMai3Account,
-        poolUsdtAccount,
-        poolMai3Account,
-      } = context;
-      const taskId = 21;
+      const beforeApplicant =
+        await context.provider.connection.getTokenAccountBalance(
+          applicantUsdtAccount
+        );
+      const beforeRequester =
+        await context.provider.connection.getTokenAccountBalance(
+          userUsdtAccount
+        );
+
+      await program.methods
+        .withdraw()
+        .accounts({
+          ...withdrawAccounts,
+          requester: wallet.publicKey,
+          requesterCoinAccount: userUsdtAccount,
+        })
+        .signers([applicant])
+        .rpc();
+
+      const afterApplicant =
+        await context.provider.connection.getTokenAccountBalance(
+          applicantUsdtAccount
+        );
+      const afterRequester =
+        await context.provider.connection.getTokenAccountBalance(
+          userUsdtAccount
+        );
+
+      const adminAccount = await program.account.admin.fetch(admin);
+      const taskFee = Math.floor(
+        (awardedAmount * adminAccount.taskFeeBps) / 10000
+      );
+      assert.equal(
+        parseInt(afterApplicant.value.amount) -
+          parseInt(beforeApplicant.value.amount),
+        awardedAmount - taskFee
+      );
+      assert.equal(
+        parseInt(afterRequester.value.amount) -
+          parseInt(beforeRequester.value.amount),
+        taskAmount - awardedAmount
+      );
+
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.escrowAmount.toNumber(), 0);
+    });
+
+    it("Should fail when application state is not AcceptedByAcceptance", async () => {
+      const {
+        program,
+        applicant,
+        wallet,
+        admin,
+        usdtMint,
+        mai3Mint,
+        userUsdtAccount,
+        userMai3Account,
+      } = context;
 
       // Create task first
-      const [taskInfo] = PublicKey.findProgramAddressSync(
+      const taskInfo = await createTask(program, {
+        taskAmount: 1000,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 100,
+        expireTime: Math.floor(Date.now() / 1000) + 3600,
+        wallet,
+        admin,
+        userCoinAccount: userUsdtAccount,
+      });
+      unacceptedTaskInfo = taskInfo;
+
+      // Get task application PDA
+      const [taskApplication] = PublicKey.findProgramAddressSync(
         [
-          Buffer.from("task_info"),
-          new anchor.BN(taskId).toArrayLike(Buffer, "le", 8),
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
         ],
         program.programId
       );
 
+      // Apply for task
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([applicant])
+        .rpc();
+      await submitWork(program, taskInfo, applicant);
+
+      // Get applicant's USDT balance before withdrawal
+      const applicantUsdtAccount = await getAssociatedTokenAddressSync(
+        usdtMint,
+        applicant.publicKey,
+        true,
+        TOKEN_PROGRAM_ID,
+        ASSOCIATED_TOKEN_PROGRAM_ID
+      );
+      try {
+        // Try to withdraw without acceptance
+        await program.methods
+          .withdraw()
+          .accounts({
+            user: applicant.publicKey,
+            taskApplication: taskApplication,
+            taskInfo: taskInfo,
+            coinMint: usdtMint,
+            userCoinAccount: applicantUsdtAccount,
+            requester: null,
+            requesterCoinAccount: null,
+            inviter: null,
+            inviterCoinAccount: null,
+            inviterReferral: null,
+            secondLevelCoinAccount: null,
+            secondLevelReferral: null,
+            thirdLevelCoinAccount: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: SYSVAR_RENT_PUBKEY,
+            admin: admin,
+          })
+          .signers([applicant])
+          .rpc();
+        assert.fail(
+          "Should have failed when application state is not AcceptedByAcceptance"
+        );
+      } catch (error) {
+        assert.include(error.message, "InvalidApplicationState");
+      }
+    });
+
+    it("Should fail when wrong applicant tries to withdraw", async () => {
+      const {
+        program,
+        applicant,
+        wallet,
+        admin,
+        usdtMint,
+        mai3Mint,
+        userUsdtAccount,
+        userMai3Account,
+      } = context;
+      // Task created by the previous test
+      const taskInfo = unacceptedTaskInfo;
+
       // Get task application PDA
       const [taskApplication] = PublicKey.findProgramAddressSync(
         [
@@ -915,7 +1635,7 @@ describe("Task Trader", () => {
       );
 
       await program.methods
-        .verifyTaskApplication(true)
+        .verifyTaskApplication(true, null, null, null)
         .accounts({
           taskApplication,
           taskInfo,
@@ -932,19 +1652,21 @@ describe("Task Trader", () => {
             user: wallet.publicKey,
             taskApplication: taskApplication,
             taskInfo: taskInfo,
-            poolAuthority: poolAuthority,
             coinMint: usdtMint,
             userCoinAccount: userUsdtAccount,
+            requester: null,
+            requesterCoinAccount: null,
             inviter: null,
             inviterCoinAccount: null,
-            poolCoinAccount: poolUsdtAccount,
+            inviterReferral: null,
+            secondLevelCoinAccount: null,
+            secondLevelReferral: null,
+            thirdLevelCoinAccount: null,
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
             rent: SYSVAR_RENT_PUBKEY,
             admin: admin,
-            feeReceiver: wallet.publicKey,
-            feeReceiverCoinAccount: userUsdtAccount,
           })
           .rpc();
         assert.fail(
@@ -955,4 +1677,1956 @@ describe("Task Trader", () => {
       }
     });
   });
+
+  describe("Task Lifecycle", () => {
+    it("Should reject applications while a task is paused", async () => {
+      const { program, wallet, applicant, usdtMint } = context;
+
+      const taskInfo = await createTask(program, {
+        taskAmount: 1_000_000,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin: context.admin,
+        userCoinAccount: context.userUsdtAccount,
+      });
+
+      await program.methods
+        .updateTaskState({ paused: {} })
+        .accounts({ user: wallet.publicKey, taskInfo })
+        .signers([wallet])
+        .rpc();
+
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .applyTask(null)
+          .accounts({
+            taskInfo,
+            taskApplication,
+            applicant: applicant.publicKey,
+            systemProgram: SystemProgram.programId,
+            applicantSbtInfo: null,
+            inviterSbtInfo: null,
+          })
+          .signers([applicant])
+          .rpc();
+        assert.fail("Should have failed when applying to a paused task");
+      } catch (error) {
+        assert.include(error.message, "InvalidTaskState");
+      }
+
+      await program.methods
+        .updateTaskState({ open: {} })
+        .accounts({ user: wallet.publicKey, taskInfo })
+        .signers([wallet])
+        .rpc();
+
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.deepEqual(taskInfoAccount.state, { open: {} });
+    });
+
+    it("Should cancel a task and refund the whole escrow", async () => {
+      const { program, provider, wallet, usdtMint, userUsdtAccount } =
+        context;
+
+      const taskAmount = 1_000_000;
+      const rewards = 100_000;
+      const takerNum = 3;
+      const taskInfo = await createTask(program, {
+        taskAmount,
+        takerNum,
+        coinMint: usdtMint,
+        rewards,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin: context.admin,
+        userCoinAccount: userUsdtAccount,
+      });
+
+      const beforeBalance = await provider.connection.getTokenAccountBalance(
+        userUsdtAccount
+      );
+
+      await program.methods
+        .cancelTask()
+        .accounts({
+          user: wallet.publicKey,
+          taskInfo,
+          coinMint: usdtMint,
+          userCoinAccount: userUsdtAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([wallet])
+        .rpc();
+
+      const afterBalance = await provider.connection.getTokenAccountBalance(
+        userUsdtAccount
+      );
+      assert.equal(
+        parseInt(afterBalance.value.amount) -
+          parseInt(beforeBalance.value.amount),
+        (taskAmount + rewards) * takerNum
+      );
+
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.deepEqual(taskInfoAccount.state, { cancelled: {} });
+    });
+
+    it("Should close a task and keep escrow for accepted applicants", async () => {
+      const {
+        program,
+        provider,
+        wallet,
+        applicant,
+        usdtMint,
+        userUsdtAccount,
+      } = context;
+
+      const taskAmount = 1_000_000;
+      const takerNum = 3;
+      const taskInfo = await createTask(program, {
+        taskAmount,
+        takerNum,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin: context.admin,
+        userCoinAccount: userUsdtAccount,
+      });
+
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([applicant])
+        .rpc();
+      await submitWork(program, taskInfo, applicant);
+
+      await program.methods
+        .verifyTaskApplication(true, null, null, null)
+        .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+        .signers([wallet])
+        .rpc();
+
+      const beforeBalance = await provider.connection.getTokenAccountBalance(
+        userUsdtAccount
+      );
+
+      const closeAccounts = {
+        user: wallet.publicKey,
+        taskInfo,
+        coinMint: usdtMint,
+        userCoinAccount: userUsdtAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      };
+
+      try {
+        await program.methods
+          .cancelTask()
+          .accounts(closeAccounts)
+          .signers([wallet])
+          .rpc();
+        assert.fail("Should have failed when cancelling an accepted task");
+      } catch (error) {
+        assert.include(error.message, "InvalidTaskState");
+      }
+
+      await program.methods
+        .closeTask()
+        .accounts(closeAccounts)
+        .signers([wallet])
+        .rpc();
+
+      const afterBalance = await provider.connection.getTokenAccountBalance(
+        userUsdtAccount
+      );
+      assert.equal(
+        parseInt(afterBalance.value.amount) -
+          parseInt(beforeBalance.value.amount),
+        taskAmount * (takerNum - 1)
+      );
+
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.deepEqual(taskInfoAccount.state, { closed: {} });
+      assert.equal(taskInfoAccount.acceptedCount.toNumber(), 1);
+    });
+
+    it("Should top up and reduce an open task", async () => {
+      const {
+        program,
+        provider,
+        wallet,
+        applicant,
+        usdtMint,
+        userUsdtAccount,
+      } = context;
+
+      const taskAmount = 1_000_000;
+      const rewards = 100_000;
+      const taskInfo = await createTask(program, {
+        taskAmount,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin: context.admin,
+        userCoinAccount: userUsdtAccount,
+      });
+
+      const adjustAccounts = {
+        user: wallet.publicKey,
+        taskInfo,
+        coinMint: usdtMint,
+        userCoinAccount: userUsdtAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      };
+
+      await program.methods
+        .increaseTakerNum(new anchor.BN(2))
+        .accounts(adjustAccounts)
+        .signers([wallet])
+        .rpc();
+      await program.methods
+        .increaseRewards(new anchor.BN(50_000))
+        .accounts(adjustAccounts)
+        .signers([wallet])
+        .rpc();
+
+      let taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.takerNum.toNumber(), 3);
+      assert.equal(taskInfoAccount.rewards.toNumber(), rewards + 50_000);
+      assert.equal(
+        taskInfoAccount.escrowAmount.toNumber(),
+        (taskAmount + rewards + 50_000) * 3
+      );
+
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([applicant])
+        .rpc();
+      await submitWork(program, taskInfo, applicant);
+      await program.methods
+        .verifyTaskApplication(true, null, null, null)
+        .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+        .signers([wallet])
+        .rpc();
+
+      try {
+        await program.methods
+          .decreaseTaskAmount(new anchor.BN(100_000))
+          .accounts(adjustAccounts)
+          .signers([wallet])
+          .rpc();
+        assert.fail("Should have failed when undercutting an accepted applicant");
+      } catch (error) {
+        assert.include(error.message, "UndercutsAcceptedApplications");
+      }
+      try {
+        await program.methods
+          .decreaseTakerNum(new anchor.BN(3))
+          .accounts(adjustAccounts)
+          .signers([wallet])
+          .rpc();
+        assert.fail("Should have failed when removing an accepted seat");
+      } catch (error) {
+        assert.include(error.message, "UndercutsAcceptedApplications");
+      }
+
+      const beforeBalance = await provider.connection.getTokenAccountBalance(
+        userUsdtAccount
+      );
+      await program.methods
+        .decreaseTakerNum(new anchor.BN(2))
+        .accounts(adjustAccounts)
+        .signers([wallet])
+        .rpc();
+      const afterBalance = await provider.connection.getTokenAccountBalance(
+        userUsdtAccount
+      );
+      assert.equal(
+        parseInt(afterBalance.value.amount) -
+          parseInt(beforeBalance.value.amount),
+        (taskAmount + rewards + 50_000) * 2
+      );
+
+      taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.takerNum.toNumber(), 1);
+      assert.equal(
+        taskInfoAccount.escrowAmount.toNumber(),
+        taskAmount + rewards + 50_000
+      );
+    });
+
+    it("Should fail to create a task with an expired deadline", async () => {
+      const { program, wallet, usdtMint } = context;
+
+      try {
+        await createTask(program, {
+          taskAmount: 1_000_000,
+          takerNum: 1,
+          coinMint: usdtMint,
+          rewards: 0,
+          expireTime: Math.floor(Date.now() / 1000) - 60,
+          wallet,
+          admin: context.admin,
+          userCoinAccount: context.userUsdtAccount,
+        });
+        assert.fail("Should have failed when the deadline is in the past");
+      } catch (error) {
+        assert.include(error.message, "InvalidExpireTime");
+      }
+    });
+
+    it("Should let anyone reclaim the escrow of an expired task", async () => {
+      const {
+        program,
+        provider,
+        wallet,
+        applicant,
+        usdtMint,
+        userUsdtAccount,
+      } = context;
+
+      const taskAmount = 1_000_000;
+      const takerNum = 2;
+      const taskInfo = await createTask(program, {
+        taskAmount,
+        takerNum,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 2,
+        wallet,
+        admin: context.admin,
+        userCoinAccount: userUsdtAccount,
+      });
+
+      const reclaim = () =>
+        program.methods
+          .reclaimExpiredTask()
+          .accounts({
+            payer: applicant.publicKey,
+            taskInfo,
+            requester: wallet.publicKey,
+            coinMint: usdtMint,
+            requesterCoinAccount: userUsdtAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([applicant])
+          .rpc();
+
+      try {
+        await reclaim();
+        assert.fail("Should have failed before the task expired");
+      } catch (error) {
+        assert.include(error.message, "TaskNotExpired");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+
+      const beforeBalance = await provider.connection.getTokenAccountBalance(
+        userUsdtAccount
+      );
+      await reclaim();
+      const afterBalance = await provider.connection.getTokenAccountBalance(
+        userUsdtAccount
+      );
+
+      assert.equal(
+        parseInt(afterBalance.value.amount) -
+          parseInt(beforeBalance.value.amount),
+        taskAmount * takerNum
+      );
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.deepEqual(taskInfoAccount.state, { closed: {} });
+    });
+  });
+
+  describe("SBT gate", () => {
+    it("Should reject applicants without a qualifying SBT", async () => {
+      const { program, wallet, admin, applicant, usdtMint, userUsdtAccount } =
+        context;
+
+      const taskInfo = await createTask(program, {
+        taskAmount: 1_000_000,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin,
+        userCoinAccount: userUsdtAccount,
+        sbtGate: { minScore: new anchor.BN(10), requireInviterSbt: false },
+      });
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      const [applicantSbtInfo] = PublicKey.findProgramAddressSync(
+        [Buffer.from("sbt_info"), applicant.publicKey.toBuffer()],
+        new PublicKey("GwvQ53QTu1xz3XXYfG5m5jEqwhMBvVBudPS8TUuFYnhT")
+      );
+
+      for (const sbtInfo of [null, applicantSbtInfo]) {
+        try {
+          await program.methods
+            .applyTask(null)
+            .accounts({
+              taskInfo,
+              taskApplication,
+              applicant: applicant.publicKey,
+              applicantSbtInfo: sbtInfo,
+              inviterSbtInfo: null,
+              systemProgram: SystemProgram.programId,
+            })
+            .signers([applicant])
+            .rpc();
+          assert.fail("Should have failed without a minted SBT");
+        } catch (error) {
+          assert.include(error.message, "ApplicantNotAllowed");
+        }
+      }
+    });
+  });
+
+  describe("Milestones", () => {
+    it("Should reject milestones that do not sum to the task amount", async () => {
+      const { program, wallet, admin, usdtMint, userUsdtAccount } = context;
+
+      try {
+        await createTask(program, {
+          taskAmount: 3_000_000,
+          takerNum: 1,
+          coinMint: usdtMint,
+          rewards: 0,
+          expireTime: Math.floor(Date.now() / 1000) + 86400,
+          wallet,
+          admin,
+          userCoinAccount: userUsdtAccount,
+          milestones: [1_000_000, 1_000_000],
+        });
+        assert.fail("Should have failed with mismatching milestones");
+      } catch (error) {
+        assert.include(error.message, "InvalidMilestones");
+      }
+    });
+
+    it("Should pay approved milestones one at a time", async () => {
+      const {
+        program,
+        provider,
+        wallet,
+        admin,
+        applicant,
+        usdtMint,
+        userUsdtAccount,
+      } = context;
+
+      const taskInfo = await createTask(program, {
+        taskAmount: 3_000_000,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin,
+        userCoinAccount: userUsdtAccount,
+        milestones: [1_000_000, 2_000_000],
+      });
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([applicant])
+        .rpc();
+      await submitWork(program, taskInfo, applicant);
+
+      const approve = (milestoneIndex: number) =>
+        program.methods
+          .verifyTaskApplication(true, null, milestoneIndex, null)
+          .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+          .signers([wallet])
+          .rpc();
+      const applicantUsdtAccount = getAssociatedTokenAddressSync(
+        usdtMint,
+        applicant.publicKey
+      );
+      const withdraw = () =>
+        program.methods
+          .withdraw()
+          .accounts({
+            user: applicant.publicKey,
+            taskApplication,
+            taskInfo,
+            coinMint: usdtMint,
+            userCoinAccount: applicantUsdtAccount,
+            requester: null,
+            requesterCoinAccount: null,
+            inviter: null,
+            inviterCoinAccount: null,
+            inviterReferral: null,
+            secondLevelCoinAccount: null,
+            secondLevelReferral: null,
+            thirdLevelCoinAccount: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: SYSVAR_RENT_PUBKEY,
+            admin,
+          })
+          .signers([applicant])
+          .rpc();
+      const applicantBalance = async () => {
+        const info = await provider.connection.getAccountInfo(
+          applicantUsdtAccount
+        );
+        if (!info) return 0;
+        const balance = await provider.connection.getTokenAccountBalance(
+          applicantUsdtAccount
+        );
+        return parseInt(balance.value.amount);
+      };
+      const adminAccount = await program.account.admin.fetch(admin);
+      const afterFee = (amount: number) =>
+        amount - Math.floor((amount * adminAccount.taskFeeBps) / 10000);
+
+      // Milestones are approved strictly in order
+      try {
+        await approve(1);
+        assert.fail("Should have failed when skipping a milestone");
+      } catch (error) {
+        assert.include(error.message, "InvalidMilestone");
+      }
+
+      await approve(0);
+      const beforeFirst = await applicantBalance();
+      await withdraw();
+      assert.equal((await applicantBalance()) - beforeFirst, afterFee(1_000_000));
+
+      try {
+        await withdraw();
+        assert.fail("Should have failed without a newly approved milestone");
+      } catch (error) {
+        assert.include(error.message, "NoApprovedMilestone");
+      }
+
+      // Closing the task keeps the remaining milestone approvable
+      await program.methods
+        .closeTask()
+        .accounts({
+          user: wallet.publicKey,
+          taskInfo,
+          coinMint: usdtMint,
+          userCoinAccount: userUsdtAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([wallet])
+        .rpc();
+      try {
+        await program.methods
+          .verifyTaskApplication(false, null, null, null)
+          .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+          .signers([wallet])
+          .rpc();
+        assert.fail("Should have failed to reject on a closed task");
+      } catch (error) {
+        assert.include(error.message, "InvalidTaskState");
+      }
+
+      // Batches take the same milestone approvals on a closed task
+      const [applicantStats] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("user_stats"),
+          applicant.publicKey.toBuffer(),
+          usdtMint.toBuffer(),
+        ],
+        program.programId
+      );
+      const reviewInBatch = (isAccepted: boolean, milestoneIndex: number | null) =>
+        program.methods
+          .batchVerifyApplications([
+            {
+              isAccepted,
+              rejectionReason: null,
+              milestoneIndex,
+              awardedAmount: null,
+            },
+          ])
+          .accounts({ taskInfo, user: wallet.publicKey })
+          .remainingAccounts(
+            [taskApplication, applicantStats].map((pubkey) => ({
+              pubkey,
+              isSigner: false,
+              isWritable: true,
+            }))
+          )
+          .signers([wallet])
+          .rpc();
+      // A rejection in a batch is skipped rather than applied
+      await reviewInBatch(false, null);
+      const stillAccepted = await program.account.taskApplication.fetch(
+        taskApplication
+      );
+      assert.deepEqual(stillAccepted.state, { acceptedByAcceptance: {} });
+
+      await reviewInBatch(true, 1);
+      const beforeSecond = await applicantBalance();
+      await withdraw();
+      assert.equal(
+        (await applicantBalance()) - beforeSecond,
+        afterFee(2_000_000)
+      );
+
+      const application = await program.account.taskApplication.fetch(
+        taskApplication
+      );
+      assert.deepEqual(application.state, { withdrawed: {} });
+      assert.equal(application.paidMilestones, 2);
+    });
+  });
+
+  describe("Vesting", () => {
+    it("Should only release the vested part of the payout", async () => {
+      const {
+        program,
+        provider,
+        wallet,
+        admin,
+        applicant,
+        usdtMint,
+        userUsdtAccount,
+      } = context;
+
+      const acceptWithVesting = async (cliff: number, duration: number) => {
+        const taskInfo = await createTask(program, {
+          taskAmount: 1_000_000,
+          takerNum: 1,
+          coinMint: usdtMint,
+          rewards: 0,
+          expireTime: Math.floor(Date.now() / 1000) + 86400,
+          wallet,
+          admin,
+          userCoinAccount: userUsdtAccount,
+          vesting: {
+            cliff: new anchor.BN(cliff),
+            duration: new anchor.BN(duration),
+          },
+        });
+        const [taskApplication] = PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("task_application"),
+            taskInfo.toBuffer(),
+            applicant.publicKey.toBuffer(),
+          ],
+          program.programId
+        );
+        await program.methods
+          .applyTask(null)
+          .accounts({
+            taskInfo,
+            taskApplication,
+            applicant: applicant.publicKey,
+            systemProgram: SystemProgram.programId,
+            applicantSbtInfo: null,
+            inviterSbtInfo: null,
+          })
+          .signers([applicant])
+          .rpc();
+        await submitWork(program, taskInfo, applicant);
+        await program.methods
+          .verifyTaskApplication(true, null, null, null)
+          .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+          .signers([wallet])
+          .rpc();
+        return { taskInfo, taskApplication };
+      };
+      const applicantUsdtAccount = getAssociatedTokenAddressSync(
+        usdtMint,
+        applicant.publicKey
+      );
+      const withdraw = (taskInfo: PublicKey, taskApplication: PublicKey) =>
+        program.methods
+          .withdraw()
+          .accounts({
+            user: applicant.publicKey,
+            taskApplication,
+            taskInfo,
+            coinMint: usdtMint,
+            userCoinAccount: applicantUsdtAccount,
+            requester: null,
+            requesterCoinAccount: null,
+            inviter: null,
+            inviterCoinAccount: null,
+            inviterReferral: null,
+            secondLevelCoinAccount: null,
+            secondLevelReferral: null,
+            thirdLevelCoinAccount: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: SYSVAR_RENT_PUBKEY,
+            admin,
+          })
+          .signers([applicant])
+          .rpc();
+
+      // A cliff longer than the duration is rejected
+      try {
+        await acceptWithVesting(7200, 3600);
+        assert.fail("Should have failed with a cliff past the duration");
+      } catch (error) {
+        assert.include(error.message, "InvalidVesting");
+      }
+
+      // Nothing can be claimed before the cliff
+      const locked = await acceptWithVesting(3600, 7200);
+      try {
+        await withdraw(locked.taskInfo, locked.taskApplication);
+        assert.fail("Should have failed before the cliff");
+      } catch (error) {
+        assert.include(error.message, "NothingVested");
+      }
+
+      // The task amount is locked while an accepted application vests
+      try {
+        await program.methods
+          .increaseTaskAmount(new anchor.BN(100_000))
+          .accounts({
+            user: wallet.publicKey,
+            taskInfo: locked.taskInfo,
+            coinMint: usdtMint,
+            userCoinAccount: userUsdtAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([wallet])
+          .rpc();
+        assert.fail("Should have failed while the payout vests");
+      } catch (error) {
+        assert.include(error.message, "VestingInProgress");
+      }
+
+      // Once the duration has elapsed the whole payout is claimable
+      const vested = await acceptWithVesting(0, 2);
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      const before = await provider.connection.getTokenAccountBalance(
+        applicantUsdtAccount
+      );
+      await withdraw(vested.taskInfo, vested.taskApplication);
+      const after = await provider.connection.getTokenAccountBalance(
+        applicantUsdtAccount
+      );
+
+      const adminAccount = await program.account.admin.fetch(admin);
+      const expected =
+        1_000_000 - Math.floor((1_000_000 * adminAccount.taskFeeBps) / 10000);
+      assert.equal(
+        parseInt(after.value.amount) - parseInt(before.value.amount),
+        expected
+      );
+      const application = await program.account.taskApplication.fetch(
+        vested.taskApplication
+      );
+      assert.deepEqual(application.state, { withdrawed: {} });
+      assert.equal(application.claimedAmount.toNumber(), expected);
+
+      // A claim midway releases part of the payout, a later claim the rest
+      const partial = await acceptWithVesting(0, 8);
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+      await withdraw(partial.taskInfo, partial.taskApplication);
+      const firstClaim = await program.account.taskApplication.fetch(
+        partial.taskApplication
+      );
+      assert.isAbove(firstClaim.claimedAmount.toNumber(), 0);
+      assert.isBelow(firstClaim.claimedAmount.toNumber(), expected);
+      assert.notDeepEqual(firstClaim.state, { withdrawed: {} });
+
+      await new Promise((resolve) => setTimeout(resolve, 7000));
+      const beforeSecondClaim = await provider.connection.getTokenAccountBalance(
+        applicantUsdtAccount
+      );
+      await withdraw(partial.taskInfo, partial.taskApplication);
+      const afterSecondClaim = await provider.connection.getTokenAccountBalance(
+        applicantUsdtAccount
+      );
+      assert.equal(
+        parseInt(afterSecondClaim.value.amount) -
+          parseInt(beforeSecondClaim.value.amount),
+        expected - firstClaim.claimedAmount.toNumber()
+      );
+      const secondClaim = await program.account.taskApplication.fetch(
+        partial.taskApplication
+      );
+      assert.deepEqual(secondClaim.state, { withdrawed: {} });
+      assert.equal(secondClaim.claimedAmount.toNumber(), expected);
+    });
+  });
+
+  describe("Referrals", () => {
+    it("Should split rewards across referral levels", async () => {
+      const {
+        program,
+        provider,
+        wallet,
+        admin,
+        usdtMint,
+        userUsdtAccount,
+      } = context;
+
+      const setSplits = (splits: number[]) =>
+        program.methods
+          .updateReferralSplits(splits)
+          .accounts({ payer: wallet.publicKey, admin })
+          .signers([wallet])
+          .rpc();
+      try {
+        await setSplits([7_000, 3_000, 1]);
+        assert.fail("Should have failed when splits exceed the rewards");
+      } catch (error) {
+        assert.include(error.message, "InvalidReferralSplits");
+      }
+      await setSplits([7_000, 3_000, 0]);
+
+      // The wallet invites the applicant and was itself invited by `secondLevel`
+      const secondLevel = Keypair.generate();
+      const secondLevelUsdtAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        wallet,
+        usdtMint,
+        secondLevel.publicKey
+      );
+      const [walletReferral] = PublicKey.findProgramAddressSync(
+        [Buffer.from("referral"), wallet.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .registerReferral(secondLevel.publicKey)
+        .accounts({
+          user: wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([wallet])
+        .rpc();
+      // The wallet's referral is registered, the applicant's points at the wallet
+      const applicant = await referredApplicant(program, wallet.publicKey);
+
+      const rewards = 1_000_000;
+      const taskInfo = await createTask(program, {
+        taskAmount: 1_000_000,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin,
+        userCoinAccount: userUsdtAccount,
+      });
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([applicant])
+        .rpc();
+      await submitWork(program, taskInfo, applicant);
+      await program.methods
+        .verifyTaskApplication(true, null, null, null)
+        .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+        .signers([wallet])
+        .rpc();
+
+      const withdrawAccounts = {
+        user: applicant.publicKey,
+        taskApplication,
+        taskInfo,
+        coinMint: usdtMint,
+        userCoinAccount: getAssociatedTokenAddressSync(
+          usdtMint,
+          applicant.publicKey
+        ),
+        requester: null,
+        requesterCoinAccount: null,
+        inviter: wallet.publicKey,
+        inviterCoinAccount: userUsdtAccount,
+        inviterReferral: null,
+        secondLevelCoinAccount: null,
+        secondLevelReferral: null,
+        thirdLevelCoinAccount: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+        admin,
+      };
+
+      // Existing upline levels cannot be skipped by leaving out their referrals
+      try {
+        await program.methods
+          .withdraw()
+          .accounts(withdrawAccounts)
+          .signers([applicant])
+          .rpc();
+        assert.fail("Should have failed without the inviter's referral");
+      } catch (error) {
+        assert.include(error.message, "InvalidInviter");
+      }
+
+      // A level account that does not belong to the chain is rejected
+      try {
+        await program.methods
+          .withdraw()
+          .accounts({
+            ...withdrawAccounts,
+            inviterReferral: walletReferral,
+            secondLevelCoinAccount: userUsdtAccount,
+          })
+          .signers([applicant])
+          .rpc();
+        assert.fail("Should have failed with a mismatching level account");
+      } catch (error) {
+        assert.include(error.message, "InvalidInviter");
+      }
+
+      await program.methods
+        .withdraw()
+        .accounts({
+          ...withdrawAccounts,
+          inviterReferral: walletReferral,
+          secondLevelCoinAccount: secondLevelUsdtAccount,
+          secondLevelReferral: referralAddress(program, secondLevel.publicKey),
+        })
+        .signers([applicant])
+        .rpc();
+
+      const adminAccount = await program.account.admin.fetch(admin);
+      const secondLevelShare = (rewards * 3_000) / 10_000;
+      const secondLevelBalance =
+        await provider.connection.getTokenAccountBalance(
+          secondLevelUsdtAccount
+        );
+      assert.equal(
+        parseInt(secondLevelBalance.value.amount),
+        secondLevelShare -
+          Math.floor((secondLevelShare * adminAccount.rewardFeeBps) / 10000)
+      );
+
+      await setSplits([10_000, 0, 0]);
+    });
+  });
+
+  describe("Disputes", () => {
+    it("Should let the arbiter split a disputed share", async () => {
+      const {
+        program,
+        provider,
+        wallet,
+        admin,
+        applicant,
+        usdtMint,
+        userUsdtAccount,
+      } = context;
+
+      const taskAmount = 2_000_000;
+      const taskInfo = await createTask(program, {
+        taskAmount,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin,
+        userCoinAccount: userUsdtAccount,
+      });
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([applicant])
+        .rpc();
+      const raiseDispute = () =>
+        program.methods
+          .raiseDispute()
+          .accounts({
+            applicant: applicant.publicKey,
+            taskInfo,
+            taskApplication,
+          })
+          .signers([applicant])
+          .rpc();
+
+      // Only delivered work can be disputed
+      try {
+        await raiseDispute();
+        assert.fail("Should have failed without submitted work");
+      } catch (error) {
+        assert.include(error.message, "InvalidApplicationState");
+      }
+      await submitWork(program, taskInfo, applicant);
+      await program.methods
+        .verifyTaskApplication(false, 1, null, null)
+        .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+        .signers([wallet])
+        .rpc();
+
+      await raiseDispute();
+
+      // The disputed share is frozen: the requester can neither re-review nor cancel
+      try {
+        await program.methods
+          .verifyTaskApplication(false, 2, null, null)
+          .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+          .signers([wallet])
+          .rpc();
+        assert.fail("Should have failed while the application is disputed");
+      } catch (error) {
+        assert.include(error.message, "InvalidApplicationState");
+      }
+
+      const applicantUsdtAccount = getAssociatedTokenAddressSync(
+        usdtMint,
+        applicant.publicKey
+      );
+      const resolve = (arbiter: Keypair) =>
+        program.methods
+          .resolveDispute(new anchor.BN(taskAmount / 2))
+          .accounts({
+            arbiter: arbiter.publicKey,
+            taskInfo,
+            taskApplication,
+            applicant: applicant.publicKey,
+            requester: wallet.publicKey,
+            coinMint: usdtMint,
+            applicantCoinAccount: applicantUsdtAccount,
+            requesterCoinAccount: userUsdtAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([arbiter])
+          .rpc();
+
+      try {
+        await resolve(applicant);
+        assert.fail("Should have failed for a non-arbiter");
+      } catch (error) {
+        assert.include(error.message, "InvalidArbiter");
+      }
+
+      const [applicantStats] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("user_stats"),
+          applicant.publicKey.toBuffer(),
+          usdtMint.toBuffer(),
+        ],
+        program.programId
+      );
+      const statsBefore = await program.account.userStats.fetch(applicantStats);
+      const before = await provider.connection.getTokenAccountBalance(
+        applicantUsdtAccount
+      );
+      await resolve(wallet);
+      const after = await provider.connection.getTokenAccountBalance(
+        applicantUsdtAccount
+      );
+      const adminAccount = await program.account.admin.fetch(admin);
+      const half = taskAmount / 2;
+      assert.equal(
+        parseInt(after.value.amount) - parseInt(before.value.amount),
+        half - Math.floor((half * adminAccount.taskFeeBps) / 10000)
+      );
+
+      // The settled share counts as a completed task in the applicant's stats
+      const statsAfter = await program.account.userStats.fetch(applicantStats);
+      assert.equal(
+        statsAfter.totalEarned.toNumber() - statsBefore.totalEarned.toNumber(),
+        half - Math.floor((half * adminAccount.taskFeeBps) / 10000)
+      );
+      assert.equal(
+        statsAfter.tasksCompleted.toNumber(),
+        statsBefore.tasksCompleted.toNumber() + 1
+      );
+
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.escrowAmount.toNumber(), 0);
+      const application = await program.account.taskApplication.fetch(
+        taskApplication
+      );
+      assert.deepEqual(application.state, { withdrawed: {} });
+    });
+
+    it("Should keep rejected work disputable after the task closes", async () => {
+      const {
+        program,
+        provider,
+        wallet,
+        admin,
+        applicant,
+        usdtMint,
+        userUsdtAccount,
+      } = context;
+
+      const taskAmount = 1_000_000;
+      const taskInfo = await createTask(program, {
+        taskAmount,
+        takerNum: 2,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin,
+        userCoinAccount: userUsdtAccount,
+      });
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([applicant])
+        .rpc();
+      await submitWork(program, taskInfo, applicant);
+      await program.methods
+        .verifyTaskApplication(false, 1, null, null)
+        .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+        .signers([wallet])
+        .rpc();
+
+      // The rejection opens a dispute window, which anyone can lapse once it passed
+      const rejected = await program.account.taskApplication.fetch(
+        taskApplication
+      );
+      assert.isAbove(
+        rejected.disputeDeadline.toNumber(),
+        Math.floor(Date.now() / 1000)
+      );
+      try {
+        await program.methods
+          .lapseDispute()
+          .accounts({ payer: wallet.publicKey, taskInfo, taskApplication })
+          .signers([wallet])
+          .rpc();
+        assert.fail("Should have failed within the dispute window");
+      } catch (error) {
+        assert.include(error.message, "DisputeWindowOpen");
+      }
+
+      const closeAccounts = {
+        user: wallet.publicKey,
+        taskInfo,
+        coinMint: usdtMint,
+        userCoinAccount: userUsdtAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      };
+      // Rejected work blocks cancelling, which would refund its seat
+      try {
+        await program.methods
+          .cancelTask()
+          .accounts(closeAccounts)
+          .signers([wallet])
+          .rpc();
+        assert.fail("Should have failed with disputable work");
+      } catch (error) {
+        assert.include(error.message, "InvalidTaskState");
+      }
+
+      // Closing refunds the free seat and keeps the disputable one
+      const before = await provider.connection.getTokenAccountBalance(
+        userUsdtAccount
+      );
+      await program.methods
+        .closeTask()
+        .accounts(closeAccounts)
+        .signers([wallet])
+        .rpc();
+      const after = await provider.connection.getTokenAccountBalance(
+        userUsdtAccount
+      );
+      assert.equal(
+        parseInt(after.value.amount) - parseInt(before.value.amount),
+        taskAmount
+      );
+
+      await program.methods
+        .raiseDispute()
+        .accounts({
+          applicant: applicant.publicKey,
+          taskInfo,
+          taskApplication,
+        })
+        .signers([applicant])
+        .rpc();
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.takerNum.toNumber(), 1);
+      assert.equal(taskInfoAccount.acceptedCount.toNumber(), 1);
+      assert.equal(taskInfoAccount.disputableCount.toNumber(), 0);
+      assert.equal(taskInfoAccount.escrowAmount.toNumber(), taskAmount);
+    });
+  });
+
+  describe("Batches", () => {
+    it("Should review and pay out applications in batches", async () => {
+      const { program, provider, wallet, applicant, usdtMint, userUsdtAccount } =
+        context;
+
+      const taskAmount = 1000;
+      const awardedAmount = 500;
+      const taskInfo = await createTask(program, {
+        taskAmount,
+        takerNum: 3,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 3600,
+        wallet,
+        admin: context.admin,
+        userCoinAccount: userUsdtAccount,
+      });
+
+      const takers = [applicant, Keypair.generate(), Keypair.generate()];
+      const applications: PublicKey[] = [];
+      for (const taker of takers) {
+        if (taker !== applicant) {
+          await provider.connection.confirmTransaction(
+            await provider.connection.requestAirdrop(
+              taker.publicKey,
+              anchor.web3.LAMPORTS_PER_SOL
+            )
+          );
+        }
+        const [taskApplication] = PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("task_application"),
+            taskInfo.toBuffer(),
+            taker.publicKey.toBuffer(),
+          ],
+          program.programId
+        );
+        await program.methods
+          .applyTask(null)
+          .accounts({
+            taskInfo,
+            taskApplication,
+            applicant: taker.publicKey,
+            systemProgram: SystemProgram.programId,
+            applicantSbtInfo: null,
+            inviterSbtInfo: null,
+          })
+          .signers([taker])
+          .rpc();
+        await submitWork(program, taskInfo, taker);
+        applications.push(taskApplication);
+      }
+      const statsAccounts = takers.map(
+        (taker) =>
+          PublicKey.findProgramAddressSync(
+            [
+              Buffer.from("user_stats"),
+              taker.publicKey.toBuffer(),
+              usdtMint.toBuffer(),
+            ],
+            program.programId
+          )[0]
+      );
+
+      const writable = (pubkey: PublicKey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: true,
+      });
+      const review = (
+        isAccepted: boolean,
+        rejectionReason: number | null,
+        awarded: number | null
+      ) => ({
+        isAccepted,
+        rejectionReason,
+        milestoneIndex: null,
+        awardedAmount: awarded === null ? null : new anchor.BN(awarded),
+      });
+
+      // The last entry is not an application and is skipped
+      await program.methods
+        .batchVerifyApplications([
+          review(true, null, null),
+          review(true, null, awardedAmount),
+          review(false, 3, null),
+          review(true, null, null),
+        ])
+        .accounts({ taskInfo, user: wallet.publicKey })
+        .remainingAccounts(
+          [
+            ...applications.flatMap((application, i) => [
+              application,
+              statsAccounts[i],
+            ]),
+            userUsdtAccount,
+            userUsdtAccount,
+          ].map(writable)
+        )
+        .signers([wallet])
+        .rpc();
+
+      const states = await Promise.all(
+        applications.map(async (application) => {
+          const account = await program.account.taskApplication.fetch(
+            application
+          );
+          return account.state;
+        })
+      );
+      assert.deepEqual(states, [
+        { acceptedByAcceptance: {} },
+        { acceptedByAcceptance: {} },
+        { rejected: {} },
+      ]);
+      let taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.acceptedCount.toNumber(), 2);
+
+      const takerAccounts: PublicKey[] = [];
+      for (const taker of takers) {
+        const takerAccount = getAssociatedTokenAddressSync(
+          usdtMint,
+          taker.publicKey,
+          true,
+          TOKEN_PROGRAM_ID,
+          ASSOCIATED_TOKEN_PROGRAM_ID
+        );
+        if (taker !== applicant) {
+          await createAssociatedTokenAccount(
+            provider.connection,
+            wallet,
+            usdtMint,
+            taker.publicKey,
+            { commitment: "confirmed" },
+            TOKEN_PROGRAM_ID,
+            ASSOCIATED_TOKEN_PROGRAM_ID
+          );
+        }
+        takerAccounts.push(takerAccount);
+      }
+      const balance = async (account: PublicKey) =>
+        parseInt(
+          (await provider.connection.getTokenAccountBalance(account)).value
+            .amount
+        );
+      const before = await Promise.all(takerAccounts.map(balance));
+
+      // The rejected application is skipped
+      await program.methods
+        .batchPayout()
+        .accounts({
+          payer: wallet.publicKey,
+          taskInfo,
+          coinMint: usdtMint,
+          requester: wallet.publicKey,
+          requesterCoinAccount: userUsdtAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          applications.flatMap((application, i) =>
+            [application, takerAccounts[i], statsAccounts[i]].map(writable)
+          )
+        )
+        .signers([wallet])
+        .rpc();
+
+      const after = await Promise.all(takerAccounts.map(balance));
+      const adminAccount = await program.account.admin.fetch(context.admin);
+      const afterFee = (amount: number) =>
+        amount - Math.floor((amount * adminAccount.taskFeeBps) / 10000);
+      assert.deepEqual(
+        after.map((amount, i) => amount - before[i]),
+        [afterFee(taskAmount), afterFee(awardedAmount), 0]
+      );
+
+      taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.paidCount.toNumber(), 2);
+      assert.equal(taskInfoAccount.escrowAmount.toNumber(), taskAmount);
+
+      // Reviews and payouts are recorded in the takers' stats
+      const [awardedStats, rejectedStats] = await Promise.all(
+        statsAccounts.slice(1).map((stats) => program.account.userStats.fetch(stats))
+      );
+      assert.equal(awardedStats.applicationsAccepted.toNumber(), 1);
+      assert.equal(awardedStats.tasksCompleted.toNumber(), 1);
+      assert.equal(
+        awardedStats.totalEarned.toNumber(),
+        afterFee(awardedAmount)
+      );
+      assert.equal(rejectedStats.applicationsAccepted.toNumber(), 0);
+      assert.equal(rejectedStats.applicationsRejected.toNumber(), 1);
+      assert.equal(rejectedStats.totalEarned.toNumber(), 0);
+
+      // Resubmitting work does not erase the rejection
+      await submitWork(program, taskInfo, takers[2]);
+      const resubmittedStats = await program.account.userStats.fetch(
+        statsAccounts[2]
+      );
+      assert.equal(resubmittedStats.applicationsRejected.toNumber(), 1);
+    });
+  });
+
+  describe("Attestations", () => {
+    it("Should accept and pay out with a signed attestation", async () => {
+      const { program, provider, wallet, applicant, usdtMint, userUsdtAccount } =
+        context;
+
+      const taskAmount = 1000;
+      const taskInfo = await createTask(program, {
+        taskAmount,
+        takerNum: 1,
+        coinMint: usdtMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 3600,
+        wallet,
+        admin: context.admin,
+        userCoinAccount: userUsdtAccount,
+      });
+      const { taskId } = await program.account.taskInfo.fetch(taskInfo);
+
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([applicant])
+        .rpc();
+      await submitWork(program, taskInfo, applicant);
+
+      const attestation = {
+        taskId,
+        applicant: applicant.publicKey,
+        reviewer: wallet.publicKey,
+        awardedAmount: null,
+        expiry: new anchor.BN(Math.floor(Date.now() / 1000) + 600),
+        nonce: new anchor.BN(1),
+      };
+      const signedMessage = (programId: PublicKey, boundTaskInfo: PublicKey) =>
+        Buffer.concat([
+          Buffer.from("task_trader_acceptance"),
+          programId.toBuffer(),
+          boundTaskInfo.toBuffer(),
+          program.coder.types.encode("acceptanceAttestation", attestation),
+        ]);
+      const accept = (
+        signer: Keypair,
+        message = signedMessage(program.programId, taskInfo)
+      ) =>
+        program.methods
+          .acceptWithAttestation(attestation)
+          .accounts({ applicant: applicant.publicKey, taskInfo, taskApplication })
+          .preInstructions([
+            Ed25519Program.createInstructionWithPrivateKey({
+              privateKey: signer.secretKey,
+              message,
+            }),
+          ])
+          .signers([applicant]);
+
+      // Signed by someone who is not a reviewer of the task
+      try {
+        await accept(applicant).rpc();
+        assert.fail("Should have failed with a foreign signature");
+      } catch (error) {
+        assert.include(error.message, "InvalidAttestation");
+      }
+
+      // Signatures made for another deployment or task account do not carry over
+      for (const message of [
+        signedMessage(Keypair.generate().publicKey, taskInfo),
+        signedMessage(program.programId, Keypair.generate().publicKey),
+      ]) {
+        try {
+          await accept(wallet, message).rpc();
+          assert.fail("Should have failed with a signature for another binding");
+        } catch (error) {
+          assert.include(error.message, "InvalidAttestation");
+        }
+      }
+
+      const applicantUsdtAccount = getAssociatedTokenAddressSync(
+        usdtMint,
+        applicant.publicKey
+      );
+      const withdraw = await program.methods
+        .withdraw()
+        .accounts({
+          user: applicant.publicKey,
+          taskApplication,
+          taskInfo,
+          coinMint: usdtMint,
+          userCoinAccount: applicantUsdtAccount,
+          requester: null,
+          requesterCoinAccount: null,
+          inviter: null,
+          inviterCoinAccount: null,
+          inviterReferral: null,
+          secondLevelCoinAccount: null,
+          secondLevelReferral: null,
+          thirdLevelCoinAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+          admin: context.admin,
+        })
+        .instruction();
+
+      const before = await provider.connection.getTokenAccountBalance(
+        applicantUsdtAccount
+      );
+      await accept(wallet).postInstructions([withdraw]).rpc();
+      const after = await provider.connection.getTokenAccountBalance(
+        applicantUsdtAccount
+      );
+      const adminAccount = await program.account.admin.fetch(context.admin);
+      assert.equal(
+        parseInt(after.value.amount) - parseInt(before.value.amount),
+        taskAmount - Math.floor((taskAmount * adminAccount.taskFeeBps) / 10000)
+      );
+
+      // The nonce is spent, so the same attestation cannot be replayed
+      try {
+        await accept(wallet).rpc();
+        assert.fail("Should have failed when replaying the attestation");
+      } catch (error) {
+        assert.include(error.message, "already in use");
+      }
+    });
+  });
+
+  describe("Native SOL", () => {
+    it("Should escrow and pay out native SOL", async () => {
+      const { program, provider, wallet, admin, applicant, usdtMint, mai3Mint } =
+        context;
+
+      const [supportCoinPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("support_coin")],
+        program.programId
+      );
+      await program.methods
+        .updateTaskSupportCoin([usdtMint, mai3Mint, NATIVE_MINT])
+        .accounts({
+          payer: wallet.publicKey,
+          admin,
+          supportCoin: supportCoinPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([wallet])
+        .rpc();
+
+      const taskAmount = 100_000_000; // 0.1 SOL
+      const walletWsolAccount = getAssociatedTokenAddressSync(
+        NATIVE_MINT,
+        wallet.publicKey
+      );
+      const createSolTask = () =>
+        createTask(program, {
+          taskAmount,
+          takerNum: 1,
+          coinMint: NATIVE_MINT,
+          rewards: 0,
+          expireTime: Math.floor(Date.now() / 1000) + 86400,
+          wallet,
+          admin,
+          userCoinAccount: walletWsolAccount,
+        });
+
+      // Tasks cannot be created before the mint has a treasury to settle fees in
+      try {
+        await createSolTask();
+        assert.fail("Should have failed without a treasury");
+      } catch (error) {
+        assert.include(error.message, "AccountNotInitialized");
+      }
+
+      await program.methods
+        .initializeTreasury()
+        .accounts({
+          payer: wallet.publicKey,
+          coinMint: NATIVE_MINT,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([wallet])
+        .rpc();
+      const taskInfo = await createSolTask();
+
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.equal(taskInfoAccount.escrowAmount.toNumber(), taskAmount);
+
+      const [taskApplication] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("task_application"),
+          taskInfo.toBuffer(),
+          applicant.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      await program.methods
+        .applyTask(null)
+        .accounts({
+          taskInfo,
+          taskApplication,
+          applicant: applicant.publicKey,
+          systemProgram: SystemProgram.programId,
+          applicantSbtInfo: null,
+          inviterSbtInfo: null,
+        })
+        .signers([applicant])
+        .rpc();
+      await submitWork(program, taskInfo, applicant);
+      await program.methods
+        .verifyTaskApplication(true, null, null, null)
+        .accounts({ taskApplication, taskInfo, user: wallet.publicKey })
+        .signers([wallet])
+        .rpc();
+
+      const applicantWsolAccount = getAssociatedTokenAddressSync(
+        NATIVE_MINT,
+        applicant.publicKey
+      );
+      const beforeLamports = await provider.connection.getBalance(
+        applicant.publicKey
+      );
+
+      await program.methods
+        .withdraw()
+        .accounts({
+          user: applicant.publicKey,
+          taskApplication,
+          taskInfo,
+          coinMint: NATIVE_MINT,
+          userCoinAccount: applicantWsolAccount,
+          requester: null,
+          requesterCoinAccount: null,
+          inviter: null,
+          inviterCoinAccount: null,
+          inviterReferral: null,
+          secondLevelCoinAccount: null,
+          secondLevelReferral: null,
+          thirdLevelCoinAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+          admin,
+        })
+        .signers([applicant])
+        .rpc();
+
+      // The payout arrives as lamports and the temporary wSOL account is closed
+      const adminAccount = await program.account.admin.fetch(admin);
+      const payout =
+        taskAmount - Math.floor((taskAmount * adminAccount.taskFeeBps) / 10000);
+      const afterLamports = await provider.connection.getBalance(
+        applicant.publicKey
+      );
+      assert.isAbove(afterLamports - beforeLamports, payout - 100_000);
+      assert.isNull(
+        await provider.connection.getAccountInfo(applicantWsolAccount)
+      );
+    });
+  });
+
+  // Transfer-hook mints are not covered: exercising the forwarding of their
+  // extra accounts needs a hook program deployed alongside task-trader.
+  describe("Token-2022", () => {
+    it("Should escrow the full deposit for a transfer-fee mint", async () => {
+      const { program, provider, wallet, admin, usdtMint, mai3Mint } = context;
+
+      // Token-2022 mint charging 1% on every transfer
+      const feeMintKeypair = Keypair.generate();
+      const feeMint = feeMintKeypair.publicKey;
+      const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+      await sendAndConfirmTransaction(
+        provider.connection,
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: wallet.publicKey,
+            newAccountPubkey: feeMint,
+            space: mintLen,
+            lamports:
+              await provider.connection.getMinimumBalanceForRentExemption(
+                mintLen
+              ),
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeTransferFeeConfigInstruction(
+            feeMint,
+            wallet.publicKey,
+            wallet.publicKey,
+            100,
+            BigInt(1_000_000_000),
+            TOKEN_2022_PROGRAM_ID
+          ),
+          createInitializeMintInstruction(
+            feeMint,
+            6,
+            wallet.publicKey,
+            null,
+            TOKEN_2022_PROGRAM_ID
+          )
+        ),
+        [wallet, feeMintKeypair]
+      );
+      const walletFeeMintAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        wallet,
+        feeMint,
+        wallet.publicKey,
+        { commitment: "confirmed" },
+        TOKEN_2022_PROGRAM_ID,
+        ASSOCIATED_TOKEN_PROGRAM_ID
+      );
+      await mintTo(
+        provider.connection,
+        wallet,
+        feeMint,
+        walletFeeMintAccount,
+        wallet.publicKey,
+        1_000_000_000,
+        [],
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const [supportCoinPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("support_coin")],
+        program.programId
+      );
+      await program.methods
+        .updateTaskSupportCoin([usdtMint, mai3Mint, NATIVE_MINT, feeMint])
+        .accounts({
+          payer: wallet.publicKey,
+          admin,
+          supportCoin: supportCoinPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([wallet])
+        .rpc();
+      await program.methods
+        .initializeTreasury()
+        .accounts({
+          payer: wallet.publicKey,
+          coinMint: feeMint,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([wallet])
+        .rpc();
+
+      const taskAmount = 10_000_000;
+      const takerNum = 2;
+      const taskInfo = await createTask(program, {
+        taskAmount,
+        takerNum,
+        coinMint: feeMint,
+        rewards: 0,
+        expireTime: Math.floor(Date.now() / 1000) + 86400,
+        wallet,
+        admin,
+        userCoinAccount: walletFeeMintAccount,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      });
+
+      const [taskVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("task_vault"), taskInfo.toBuffer()],
+        program.programId
+      );
+      const vaultBalance = await provider.connection.getTokenAccountBalance(
+        taskVault
+      );
+      const taskInfoAccount = await program.account.taskInfo.fetch(taskInfo);
+      assert.isAtLeast(
+        parseInt(vaultBalance.value.amount),
+        taskAmount * takerNum
+      );
+      assert.equal(
+        taskInfoAccount.escrowAmount.toString(),
+        vaultBalance.value.amount
+      );
+    });
+  });
+
+  describe("Treasury", () => {
+    it("Should let the admin collect accrued fees", async () => {
+      const { program, provider, wallet, applicant, usdtMint } = context;
+
+      const [treasury] = PublicKey.findProgramAddressSync(
+        [Buffer.from("treasury"), usdtMint.toBuffer()],
+        program.programId
+      );
+      const { accruedFees, collectedFees } =
+        await program.account.treasury.fetch(treasury);
+      assert.isAbove(accruedFees.toNumber(), 0);
+
+      const destination = getAssociatedTokenAddressSync(
+        usdtMint,
+        applicant.publicKey
+      );
+      const collect = (signer: Keypair) =>
+        program.methods
+          .collectFees()
+          .accounts({
+            payer: signer.publicKey,
+            coinMint: usdtMint,
+            destination,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([signer])
+          .rpc();
+
+      try {
+        await collect(applicant);
+        assert.fail("Should have failed for a non-admin");
+      } catch (error) {
+        assert.include(error.message, "Unauthorized");
+      }
+
+      const before = await provider.connection.getTokenAccountBalance(
+        destination
+      );
+      await collect(wallet);
+      const after = await provider.connection.getTokenAccountBalance(
+        destination
+      );
+      assert.equal(
+        parseInt(after.value.amount) - parseInt(before.value.amount),
+        accruedFees.toNumber()
+      );
+
+      const treasuryAccount = await program.account.treasury.fetch(treasury);
+      assert.equal(treasuryAccount.accruedFees.toNumber(), 0);
+      assert.equal(
+        treasuryAccount.collectedFees.toNumber(),
+        collectedFees.toNumber() + accruedFees.toNumber()
+      );
+    });
+  });
 });